@@ -0,0 +1,197 @@
+use image::{
+    imageops::{self, FilterType},
+    DynamicImage, GenericImageView, GrayImage, Luma, Rgb, RgbImage, Rgba,
+};
+
+use crate::Cell;
+
+/// Options of the transparency handling, see [`crate::SymbolizeOptions::alpha`].
+///
+/// The image is scaled with its colors weighted by their opacity,
+/// so the transparent pixels don't darken the edges. [`image::imageops::FilterType::Nearest`]
+/// picks single pixels without mixing them, so their colors are kept as they are stored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlphaOptions {
+    /// Cells less opaque than the threshold (0-255) are left blank: a space without any colors
+    pub threshold: u8,
+    /// Color the image is composited onto, no cell is left blank then
+    pub matte: Option<Rgb<u8>>,
+}
+
+impl Default for AlphaOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 128,
+            matte: None,
+        }
+    }
+}
+
+/// Scales the image to the given size, without alpha options the alpha channel is discarded.
+/// Returns the colors of the scaled image and, when the cells can be transparent, its opacity.
+pub(crate) fn resize(
+    image: DynamicImage,
+    width: u32,
+    height: u32,
+    filter_type: FilterType,
+    options: Option<&AlphaOptions>,
+) -> (RgbImage, Option<GrayImage>) {
+    let Some(options) = options else {
+        return (
+            imageops::resize(&image.into_rgb8(), width, height, filter_type),
+            None,
+        );
+    };
+
+    let mut image = image.into_rgba8();
+    if let Some(matte) = options.matte {
+        for pixel in image.pixels_mut() {
+            *pixel = composite(pixel, &matte);
+        }
+        return (
+            imageops::resize(
+                &DynamicImage::ImageRgba8(image).into_rgb8(),
+                width,
+                height,
+                filter_type,
+            ),
+            None,
+        );
+    }
+
+    // the nearest filter doesn't mix the pixels, so it's spared the rounding of premultiplying
+    let scaled = if filter_type == FilterType::Nearest {
+        imageops::resize(&image, width, height, filter_type)
+    } else {
+        for pixel in image.pixels_mut() {
+            *pixel = premultiply(pixel);
+        }
+        let mut scaled = imageops::resize(&image, width, height, filter_type);
+        for pixel in scaled.pixels_mut() {
+            *pixel = unpremultiply(pixel);
+        }
+        scaled
+    };
+
+    let opacity = GrayImage::from_fn(width, height, |x, y| Luma([scaled.get_pixel(x, y).0[3]]));
+    (DynamicImage::ImageRgba8(scaled).into_rgb8(), Some(opacity))
+}
+
+/// Leaves the cells blank where the average opacity of their pixels is below the threshold
+pub(crate) fn clear_transparent(
+    cells: Vec<Vec<Cell>>,
+    opacity: &GrayImage,
+    threshold: u8,
+) -> Vec<Vec<Cell>> {
+    let rows = cells.len().max(1) as u32;
+    let columns = cells.iter().map(Vec::len).max().unwrap_or(0).max(1) as u32;
+    let (cell_width, cell_height) = (opacity.width() / columns, opacity.height() / rows);
+
+    cells
+        .into_iter()
+        .enumerate()
+        .map(|(row_idx, row)| {
+            row.into_iter()
+                .enumerate()
+                .map(|(column, cell)| {
+                    let block = imageops::crop_imm(
+                        opacity,
+                        column as u32 * cell_width,
+                        row_idx as u32 * cell_height,
+                        cell_width,
+                        cell_height,
+                    );
+                    let sum: u32 = block.pixels().map(|(_, _, pixel)| pixel.0[0] as u32).sum();
+                    let count = (cell_width * cell_height).max(1);
+
+                    if sum / count < threshold as u32 {
+                        Cell {
+                            symbol: ' ',
+                            fg: None,
+                            bg: None,
+                        }
+                    } else {
+                        cell
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn composite(pixel: &Rgba<u8>, matte: &Rgb<u8>) -> Rgba<u8> {
+    let alpha = pixel.0[3] as u32;
+    let [red, green, blue] = [0, 1, 2].map(|idx| {
+        ((pixel.0[idx] as u32 * alpha + matte.0[idx] as u32 * (255 - alpha)) / 255) as u8
+    });
+
+    Rgba([red, green, blue, 255])
+}
+
+fn premultiply(pixel: &Rgba<u8>) -> Rgba<u8> {
+    let alpha = pixel.0[3] as u32;
+    let [red, green, blue] = [0, 1, 2].map(|idx| ((pixel.0[idx] as u32 * alpha + 127) / 255) as u8);
+
+    Rgba([red, green, blue, pixel.0[3]])
+}
+
+fn unpremultiply(pixel: &Rgba<u8>) -> Rgba<u8> {
+    let alpha = pixel.0[3] as u32;
+    if alpha == 0 {
+        return Rgba([0, 0, 0, 0]);
+    }
+    let [red, green, blue] =
+        [0, 1, 2].map(|idx| ((pixel.0[idx] as u32 * 255 + alpha / 2) / alpha).min(255) as u8);
+
+    Rgba([red, green, blue, pixel.0[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{imageops::FilterType, DynamicImage, Rgb, Rgba, RgbaImage};
+
+    use super::{resize, AlphaOptions};
+
+    fn get_half_transparent() -> DynamicImage {
+        // the transparent half stores white, as some editors do
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(4, 2, |x, _| {
+            if x < 2 {
+                Rgba([255, 0, 0, 255])
+            } else {
+                Rgba([255, 255, 255, 0])
+            }
+        }))
+    }
+
+    #[test]
+    fn weights_colors_by_opacity_when_scaling() {
+        let (colors, opacity) = resize(
+            get_half_transparent(),
+            1,
+            1,
+            FilterType::Triangle,
+            Some(&AlphaOptions::default()),
+        );
+
+        assert_eq!(*colors.get_pixel(0, 0), Rgb([255, 0, 0]));
+        assert!((127..=128).contains(&opacity.unwrap().get_pixel(0, 0).0[0]));
+    }
+
+    #[test]
+    fn composites_onto_matte() {
+        let (colors, opacity) = resize(
+            get_half_transparent(),
+            4,
+            2,
+            FilterType::Nearest,
+            Some(&AlphaOptions {
+                matte: Some(Rgb([0, 0, 255])),
+                ..Default::default()
+            }),
+        );
+
+        assert!(opacity.is_none());
+        assert_eq!(*colors.get_pixel(0, 0), Rgb([255, 0, 0]));
+        assert_eq!(*colors.get_pixel(3, 1), Rgb([0, 0, 255]));
+    }
+}
@@ -0,0 +1,211 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Seek},
+    path::Path,
+    time::Duration,
+    vec,
+};
+
+use image::{
+    codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder},
+    imageops::replace,
+    AnimationDecoder, DynamicImage, Frame, GrayImage, ImageFormat, RgbImage,
+};
+
+use crate::{
+    alpha, get_colours_with_symbols, get_grid_size, symbolize_with_options, Mode, PixelWithSymbol,
+    SymbolizeError, SymbolizeOptions, SymbolizeResult,
+};
+
+/// A symbolized frame of an animation with the time it should be shown for
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolizedFrame {
+    /// Symbolized image of the frame
+    pub result: SymbolizeResult,
+    /// How long the frame is shown before the next one
+    pub delay: Duration,
+}
+
+/// Iterator symbolizing the frames of an animation one by one, see [`crate::Symbolizer::symbolize_frames`].
+///
+/// In [`Mode::Palette`] the palette colors are picked from all of the frames at once,
+/// so every symbol stands for the same color in every frame.
+pub struct SymbolizedFrames {
+    frames: vec::IntoIter<Frame>,
+    options: SymbolizeOptions,
+    legend: Option<Vec<PixelWithSymbol>>,
+}
+
+impl SymbolizedFrames {
+    pub(crate) fn new(
+        frames: Vec<Frame>,
+        options: SymbolizeOptions,
+    ) -> Result<Self, SymbolizeError> {
+        let legend = match (&options.mode, frames.first()) {
+            (Mode::Palette(palette_options), Some(frame)) => {
+                let (columns, rows) = get_grid_size(frame.buffer().dimensions(), &options)?;
+
+                // the frames are scaled as they will be symbolized and stacked into a single image
+                let mut stacked = RgbImage::new(columns, rows * frames.len() as u32);
+                let mut stacked_opacity = None;
+                for (idx, frame) in frames.iter().enumerate() {
+                    let (scaled, opacity) = alpha::resize(
+                        DynamicImage::ImageRgba8(frame.buffer().clone()),
+                        columns,
+                        rows,
+                        options.filter_type,
+                        options.alpha.as_ref(),
+                    );
+                    replace(&mut stacked, &scaled, 0, idx as i64 * rows as i64);
+                    if let Some(opacity) = opacity {
+                        let stacked_opacity = stacked_opacity
+                            .get_or_insert_with(|| GrayImage::new(columns, stacked.height()));
+                        replace(stacked_opacity, &opacity, 0, idx as i64 * rows as i64);
+                    }
+                }
+
+                Some(get_colours_with_symbols(
+                    &stacked,
+                    stacked_opacity
+                        .as_ref()
+                        .zip(options.alpha.map(|alpha| alpha.threshold)),
+                    &options.palette,
+                    palette_options.quantizer,
+                ))
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            frames: frames.into_iter(),
+            options,
+            legend,
+        })
+    }
+}
+
+impl Iterator for SymbolizedFrames {
+    type Item = Result<SymbolizedFrame, SymbolizeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame = self.frames.next()?;
+        let delay = frame.delay().into();
+        let image = DynamicImage::ImageRgba8(frame.into_buffer());
+
+        Some(
+            symbolize_with_options(image, &self.options, self.legend.as_deref())
+                .map(|result| SymbolizedFrame { result, delay }),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.frames.size_hint()
+    }
+}
+
+/// Decodes every frame of an animated GIF, APNG or WebP image.
+/// Other images, including not animated PNG and WebP ones, are decoded as a single frame.
+pub fn load_frames<R: BufRead + Seek>(
+    reader: R,
+    format: ImageFormat,
+) -> Result<Vec<Frame>, SymbolizeError> {
+    let frames = match format {
+        ImageFormat::Gif => GifDecoder::new(reader)?.into_frames().collect_frames()?,
+        ImageFormat::Png => {
+            let decoder = PngDecoder::new(reader)?;
+            if decoder.is_apng()? {
+                decoder.apng()?.into_frames().collect_frames()?
+            } else {
+                vec![Frame::new(
+                    DynamicImage::from_decoder(decoder)?.into_rgba8(),
+                )]
+            }
+        }
+        ImageFormat::WebP => {
+            let decoder = WebPDecoder::new(reader)?;
+            if decoder.has_animation() {
+                decoder.into_frames().collect_frames()?
+            } else {
+                vec![Frame::new(
+                    DynamicImage::from_decoder(decoder)?.into_rgba8(),
+                )]
+            }
+        }
+        format => vec![Frame::new(image::load(reader, format)?.into_rgba8())],
+    };
+
+    Ok(frames)
+}
+
+/// Opens the image and decodes its frames with [`load_frames`], the format is guessed from the extension
+pub fn open_frames(path: impl AsRef<Path>) -> Result<Vec<Frame>, SymbolizeError> {
+    let path = path.as_ref();
+    let format = ImageFormat::from_path(path)?;
+
+    load_frames(BufReader::new(File::open(path)?), format)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, time::Duration};
+
+    use image::{codecs::gif::GifEncoder, open, Delay, Frame, ImageFormat, Rgba, RgbaImage};
+
+    use super::{load_frames, open_frames};
+    use crate::Symbolizer;
+
+    fn get_frame(color: Rgba<u8>, delay_ms: u32) -> Frame {
+        let mut buffer = RgbaImage::from_pixel(8, 4, Rgba([0, 0, 0, 255]));
+        for x in 0..4 {
+            for y in 0..4 {
+                buffer.put_pixel(x, y, color);
+            }
+        }
+
+        Frame::from_parts(buffer, 0, 0, Delay::from_numer_denom_ms(delay_ms, 1))
+    }
+
+    #[test]
+    fn decodes_gif_frames_with_delays() {
+        let mut gif = vec![];
+        GifEncoder::new(&mut gif)
+            .encode_frames([
+                get_frame(Rgba([255, 0, 0, 255]), 100),
+                get_frame(Rgba([0, 0, 255, 255]), 250),
+            ])
+            .unwrap();
+
+        let frames = load_frames(Cursor::new(gif), ImageFormat::Gif).unwrap();
+        let frames: Vec<_> = Symbolizer::new()
+            .scale(0.5)
+            .cell_aspect_ratio(1.0)
+            .palette(&['r', 'b', ' '])
+            .symbolize_frames(frames)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].delay, Duration::from_millis(100));
+        assert_eq!(frames[1].delay, Duration::from_millis(250));
+        // black is the most used color of both frames, so its symbol is the same in both of them
+        assert_eq!(frames[0].result.legend, frames[1].result.legend);
+        assert_eq!(
+            frames[0].result.cells[0][3].symbol,
+            frames[1].result.cells[0][3].symbol
+        );
+        assert_ne!(
+            frames[0].result.cells[0][0].symbol,
+            frames[1].result.cells[0][0].symbol
+        );
+    }
+
+    #[test]
+    fn opens_still_image_as_single_frame() {
+        let frames = open_frames("./test-data/ferris.png").unwrap();
+        let image = open("./test-data/ferris.png").unwrap();
+
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].buffer(), &image.into_rgba8());
+    }
+}
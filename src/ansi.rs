@@ -0,0 +1,302 @@
+use image::Rgb;
+
+use crate::{get_luminance, Cell, DistanceMetric};
+
+/// Defines whether the cell colors are put to the background of the cells.
+/// Cells that already have both colors, e.g. of the block modes, are kept as is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Background {
+    /// Only the glyphs are colored, the background stays the terminal one
+    #[default]
+    None,
+    /// The background is colored, the glyphs keep the default terminal color
+    Fill,
+    /// The background is colored, the glyphs are black or white, whichever contrasts more
+    Contrast,
+    /// The background is colored, the glyphs are replaced with spaces for a mosaic look
+    Solid,
+}
+
+impl Background {
+    /// Moves the color of the cell to its background if needed
+    pub(crate) fn apply(&self, cell: &Cell) -> Cell {
+        let (Some(fg), None) = (cell.fg, cell.bg) else {
+            return *cell;
+        };
+
+        match self {
+            Background::None => *cell,
+            Background::Fill => Cell {
+                fg: None,
+                bg: Some(fg),
+                ..*cell
+            },
+            Background::Contrast => {
+                let contrast = if get_luminance(&fg) >= 128.0 {
+                    Rgb([0, 0, 0])
+                } else {
+                    Rgb([255, 255, 255])
+                };
+                Cell::new(cell.symbol, contrast, Some(fg))
+            }
+            Background::Solid => Cell {
+                symbol: ' ',
+                fg: None,
+                bg: Some(fg),
+            },
+        }
+    }
+}
+
+/// Renders the row for a terminal. The colors are only set when they differ from the previous cell,
+/// and reset once at the end of the row.
+pub(crate) fn render_row(row: &[Cell], depth: ColorDepth) -> String {
+    if depth == ColorDepth::None {
+        return row.iter().map(|cell| cell.symbol).collect();
+    }
+
+    let (mut current_fg, mut current_bg) = (None, None);
+    let mut result = String::new();
+    for cell in row {
+        let (fg, bg) = (
+            cell.fg.and_then(|fg| depth.get_sgr(&fg, false)),
+            cell.bg.and_then(|bg| depth.get_sgr(&bg, true)),
+        );
+
+        let mut changes = vec![];
+        if bg != current_bg {
+            changes.push(bg.clone().unwrap_or_else(|| "49".to_string()));
+            current_bg = bg;
+        }
+        if fg != current_fg {
+            changes.push(fg.clone().unwrap_or_else(|| "39".to_string()));
+            current_fg = fg;
+        }
+
+        if !changes.is_empty() {
+            result.push_str(&format!("\x1b[{}m", changes.join(";")));
+        }
+        result.push(cell.symbol);
+    }
+
+    if current_fg.is_some() || current_bg.is_some() {
+        result.push_str("\x1b[0m");
+    }
+
+    result
+}
+
+/// Amount of colors the terminal can display, cell colors are mapped to the nearest available ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    /// 24-bit colors (`38;2;r;g;b`)
+    TrueColor,
+    /// 256 colors of the xterm palette (`38;5;n`), only the color cube and the grayscale ramp are used
+    /// as the first 16 colors differ from terminal to terminal
+    Xterm256,
+    /// 16 basic colors including the bright ones (`30`-`37`, `90`-`97`)
+    Ansi16,
+    /// 8 basic colors (`30`-`37`), e.g. for the Linux console
+    Ansi8,
+    /// No colors at all
+    None,
+}
+
+impl ColorDepth {
+    /// Guesses the color depth of the terminal by the values of `COLORTERM` and `TERM` environment variables
+    pub fn detect(colorterm: Option<&str>, term: Option<&str>) -> Self {
+        if matches!(colorterm, Some("truecolor" | "24bit")) {
+            return ColorDepth::TrueColor;
+        }
+
+        match term {
+            None | Some("" | "dumb") => ColorDepth::None,
+            Some(term) if term.contains("truecolor") || term.contains("direct") => {
+                ColorDepth::TrueColor
+            }
+            Some(term) if term.contains("256color") => ColorDepth::Xterm256,
+            Some("linux") => ColorDepth::Ansi8,
+            Some(_) => ColorDepth::Ansi16,
+        }
+    }
+
+    /// SGR parameters setting the foreground (or the background) to the nearest available color
+    pub(crate) fn get_sgr(&self, pixel: &Rgb<u8>, is_background: bool) -> Option<String> {
+        let (extended, basic, bright) = if is_background {
+            (48, 40, 100)
+        } else {
+            (38, 30, 90)
+        };
+
+        match self {
+            ColorDepth::TrueColor => Some(format!(
+                "{};2;{};{};{}",
+                extended, pixel.0[0], pixel.0[1], pixel.0[2]
+            )),
+            ColorDepth::Xterm256 => Some(format!("{};5;{}", extended, get_xterm_256(pixel))),
+            ColorDepth::Ansi16 | ColorDepth::Ansi8 => {
+                let colors = if *self == ColorDepth::Ansi16 {
+                    &ANSI_COLORS[..]
+                } else {
+                    &ANSI_COLORS[..8]
+                };
+                let idx = get_nearest(pixel, colors.iter().copied()).unwrap_or(0);
+                Some(match idx {
+                    0..=7 => format!("{}", basic + idx),
+                    _ => format!("{}", bright + idx - 8),
+                })
+            }
+            ColorDepth::None => None,
+        }
+    }
+}
+
+/// Default xterm colors of the 16 basic ANSI colors
+const ANSI_COLORS: [Rgb<u8>; 16] = [
+    Rgb([0, 0, 0]),
+    Rgb([205, 0, 0]),
+    Rgb([0, 205, 0]),
+    Rgb([205, 205, 0]),
+    Rgb([0, 0, 238]),
+    Rgb([205, 0, 205]),
+    Rgb([0, 205, 205]),
+    Rgb([229, 229, 229]),
+    Rgb([127, 127, 127]),
+    Rgb([255, 0, 0]),
+    Rgb([0, 255, 0]),
+    Rgb([255, 255, 0]),
+    Rgb([92, 92, 255]),
+    Rgb([255, 0, 255]),
+    Rgb([0, 255, 255]),
+    Rgb([255, 255, 255]),
+];
+
+/// Channel values of the 6x6x6 color cube of the xterm palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn get_xterm_256(pixel: &Rgb<u8>) -> usize {
+    let cube = pixel.0.map(|channel| {
+        get_nearest(
+            &Rgb([channel; 3]),
+            CUBE_LEVELS.iter().map(|level| Rgb([*level; 3])),
+        )
+        .unwrap_or(0)
+    });
+    let cube_color = Rgb(cube.map(|idx| CUBE_LEVELS[idx]));
+
+    // the grayscale ramp goes from 8 to 238 with the step of 10
+    let average = pixel
+        .0
+        .iter()
+        .map(|channel| *channel as usize)
+        .sum::<usize>()
+        / 3;
+    let gray = (average.saturating_sub(3) / 10).min(23);
+    let gray_level = (8 + gray * 10) as u8;
+
+    match get_nearest(pixel, [cube_color, Rgb([gray_level; 3])].into_iter()) {
+        Some(1) => 232 + gray,
+        _ => 16 + 36 * cube[0] + 6 * cube[1] + cube[2],
+    }
+}
+
+fn get_nearest(pixel: &Rgb<u8>, colors: impl Iterator<Item = Rgb<u8>>) -> Option<usize> {
+    colors
+        .map(|color| DistanceMetric::Redmean.distance(pixel, &color))
+        .enumerate()
+        .min_by(|(_, first), (_, second)| first.total_cmp(second))
+        .map(|(idx, _)| idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgb;
+
+    use super::{render_row, Background, ColorDepth};
+    use crate::Cell;
+
+    #[test]
+    fn maps_colors_to_xterm_256() {
+        let sgr = |pixel| ColorDepth::Xterm256.get_sgr(&Rgb(pixel), false).unwrap();
+
+        assert_eq!(sgr([255, 0, 0]), "38;5;196");
+        assert_eq!(sgr([0, 0, 0]), "38;5;16");
+        assert_eq!(sgr([128, 128, 128]), "38;5;244");
+        assert_eq!(sgr([247, 76, 0]), "38;5;202");
+    }
+
+    #[test]
+    fn maps_colors_to_basic_ansi() {
+        let orange = Rgb([247, 76, 0]);
+
+        assert_eq!(ColorDepth::Ansi16.get_sgr(&orange, false).unwrap(), "91");
+        assert_eq!(ColorDepth::Ansi16.get_sgr(&orange, true).unwrap(), "101");
+        assert_eq!(ColorDepth::Ansi8.get_sgr(&orange, false).unwrap(), "31");
+        assert_eq!(ColorDepth::None.get_sgr(&orange, false), None);
+    }
+
+    #[test]
+    fn detects_color_depth() {
+        assert_eq!(
+            ColorDepth::detect(Some("truecolor"), Some("xterm")),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            ColorDepth::detect(None, Some("screen-256color")),
+            ColorDepth::Xterm256
+        );
+        assert_eq!(ColorDepth::detect(None, Some("xterm")), ColorDepth::Ansi16);
+        assert_eq!(ColorDepth::detect(None, Some("linux")), ColorDepth::Ansi8);
+        assert_eq!(ColorDepth::detect(None, Some("dumb")), ColorDepth::None);
+        assert_eq!(ColorDepth::detect(None, None), ColorDepth::None);
+    }
+
+    #[test]
+    fn puts_cell_color_to_background() {
+        let (dark, light) = (Rgb([20, 20, 20]), Rgb([240, 240, 240]));
+        let cell = |fg| Cell::new('#', fg, None);
+        let apply = |background: Background, cell: Cell| {
+            let cell = background.apply(&cell);
+            (cell.symbol, cell.fg, cell.bg)
+        };
+
+        assert_eq!(apply(Background::Fill, cell(dark)), ('#', None, Some(dark)));
+        assert_eq!(
+            apply(Background::Contrast, cell(dark)),
+            ('#', Some(Rgb([255, 255, 255])), Some(dark))
+        );
+        assert_eq!(
+            apply(Background::Contrast, cell(light)),
+            ('#', Some(Rgb([0, 0, 0])), Some(light))
+        );
+        assert_eq!(
+            apply(Background::Solid, cell(dark)),
+            (' ', None, Some(dark))
+        );
+        assert_eq!(
+            apply(Background::Solid, Cell::new('▀', dark, Some(light))),
+            ('▀', Some(dark), Some(light))
+        );
+    }
+
+    #[test]
+    fn groups_colors_of_neighbouring_cells() {
+        let (red, blue) = (Rgb([255, 0, 0]), Rgb([0, 0, 255]));
+        let row = [
+            Cell::new('a', red, None),
+            Cell::new('b', red, None),
+            Cell::new('c', blue, Some(red)),
+            Cell {
+                symbol: 'd',
+                fg: None,
+                bg: None,
+            },
+        ];
+
+        assert_eq!(
+            render_row(&row, ColorDepth::Ansi16),
+            "\u{1b}[91mab\u{1b}[101;34mc\u{1b}[49;39md"
+        );
+        assert_eq!(render_row(&row, ColorDepth::None), "abcd");
+    }
+}
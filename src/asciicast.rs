@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use crate::SymbolizedFrame;
+
+/// Options of the asciicast output, see [`to_asciicast`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsciicastOptions {
+    /// Title of the recording shown by the player
+    pub title: Option<String>,
+    /// How many times the frames are repeated in the recording
+    pub loops: u32,
+}
+
+impl Default for AsciicastOptions {
+    fn default() -> Self {
+        Self {
+            title: None,
+            loops: 1,
+        }
+    }
+}
+
+/// Records the frames as an asciicast v2 file (<https://docs.asciinema.org/manual/asciicast/v2/>):
+/// a header with the terminal size and an output event for every frame at the time it's shown.
+///
+/// Every frame is drawn from the top left corner over the previous one,
+/// the recording ends after the delay of the last frame.
+pub fn to_asciicast(frames: &[SymbolizedFrame], options: &AsciicastOptions) -> String {
+    let width = frames
+        .iter()
+        .flat_map(|frame| frame.result.cells.iter().map(Vec::len))
+        .max()
+        .unwrap_or(0);
+    let height = frames
+        .iter()
+        .map(|frame| frame.result.cells.len())
+        .max()
+        .unwrap_or(0);
+
+    let mut cast = format!(
+        "{{\"version\": 2, \"width\": {}, \"height\": {}",
+        width, height
+    );
+    if let Some(title) = &options.title {
+        cast.push_str(&format!(", \"title\": \"{}\"", escape(title)));
+    }
+    cast.push_str("}\n");
+
+    // the screen is cleared once, later frames have the same size and cover the previous ones
+    let mut output = "\x1b[2J".to_string();
+    let mut time = Duration::ZERO;
+    for _ in 0..options.loops {
+        for frame in frames {
+            output.push_str("\x1b[H");
+            output.push_str(&frame.result.to_ansi_rows().join("\r\n"));
+            cast.push_str(&get_event(time, &output));
+            output.clear();
+            time += frame.delay;
+        }
+    }
+    if !frames.is_empty() {
+        cast.push_str(&get_event(time, ""));
+    }
+
+    cast
+}
+
+fn get_event(time: Duration, output: &str) -> String {
+    format!(
+        "[{:.6}, \"o\", \"{}\"]\n",
+        time.as_secs_f64(),
+        escape(output)
+    )
+}
+
+/// Escapes the text for a JSON string
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for symbol in text.chars() {
+        match symbol {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            symbol if symbol.is_control() => {
+                escaped.push_str(&format!("\\u{:04x}", symbol as u32));
+            }
+            symbol => escaped.push(symbol),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use image::Rgb;
+
+    use super::{escape, to_asciicast, AsciicastOptions};
+    use crate::{Cell, ColorDepth, SymbolizeResult, SymbolizedFrame};
+
+    fn get_frame(symbol: char, delay_ms: u64) -> SymbolizedFrame {
+        let cell = Cell::new(symbol, Rgb([255, 0, 0]), None);
+        SymbolizedFrame {
+            result: SymbolizeResult {
+                cells: vec![vec![cell; 3], vec![cell; 3]],
+                color_depth: ColorDepth::None,
+                legend: vec![],
+            },
+            delay: Duration::from_millis(delay_ms),
+        }
+    }
+
+    #[test]
+    fn records_frames_at_their_times() {
+        let cast = to_asciicast(
+            &[get_frame('"', 100), get_frame('#', 250)],
+            &AsciicastOptions {
+                title: Some("ferris".to_string()),
+                loops: 2,
+            },
+        );
+        let lines: Vec<&str> = cast.lines().collect();
+
+        assert_eq!(
+            lines,
+            [
+                "{\"version\": 2, \"width\": 3, \"height\": 2, \"title\": \"ferris\"}",
+                "[0.000000, \"o\", \"\\u001b[2J\\u001b[H\\\"\\\"\\\"\\r\\n\\\"\\\"\\\"\"]",
+                "[0.100000, \"o\", \"\\u001b[H###\\r\\n###\"]",
+                "[0.350000, \"o\", \"\\u001b[H\\\"\\\"\\\"\\r\\n\\\"\\\"\\\"\"]",
+                "[0.450000, \"o\", \"\\u001b[H###\\r\\n###\"]",
+                "[0.700000, \"o\", \"\"]",
+            ]
+        );
+    }
+
+    #[test]
+    fn escapes_control_characters() {
+        assert_eq!(escape("a\\b\x1b[0m\t"), "a\\\\b\\u001b[0m\\t");
+    }
+}
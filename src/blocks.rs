@@ -0,0 +1,376 @@
+use image::{Rgb, RgbImage};
+
+use crate::{get_average_pixel, get_luminance, Cell};
+
+/// Height of a single half-block cell in pixels
+pub(crate) const HALF_BLOCK_HEIGHT: u32 = 2;
+
+/// Width of a single quadrant, sextant or octant cell in pixels
+pub(crate) const BLOCK_WIDTH: u32 = 2;
+
+/// Luminance starting from which a pixel is drawn filled when the output is not colorized
+const MONOCHROME_THRESHOLD: f32 = 128.0;
+
+/// Packs every two vertically stacked pixels into a single half-block cell.
+///
+/// Colorized cells are always `▀` with the top pixel as a foreground and the bottom one as a background.
+/// Without colors the bright pixels are filled, so the cell is one of ` `, `▀`, `▄` or `█`.
+pub(crate) fn symbolize_half_blocks(image: &RgbImage, colorize: bool) -> Vec<Vec<Cell>> {
+    let mut result = vec![];
+    for row in 0..image.height() / HALF_BLOCK_HEIGHT {
+        let mut result_row = vec![];
+        for column in 0..image.width() {
+            let top = *image.get_pixel(column, row * HALF_BLOCK_HEIGHT);
+            let bottom = *image.get_pixel(column, row * HALF_BLOCK_HEIGHT + 1);
+
+            let symbol = if colorize {
+                '▀'
+            } else {
+                match (is_bright(&top), is_bright(&bottom)) {
+                    (false, false) => ' ',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (true, true) => '█',
+                }
+            };
+            result_row.push(Cell::new(symbol, top, Some(bottom)));
+        }
+
+        result.push(result_row);
+    }
+
+    result
+}
+
+/// Sub-cell layouts of the block element modes.
+/// Sub-pixels of a cell are numbered row by row, so the top left one is the lowest bit of a pattern.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum BlockLayout {
+    /// 2x2 quadrant blocks (U+2596–U+259F)
+    Quadrant,
+    /// 2x3 sextants of the Symbols for Legacy Computing block (U+1FB00–U+1FB3B)
+    Sextant,
+    /// 2x4 octants of the Symbols for Legacy Computing Supplement block (U+1CD00–U+1CDE5)
+    Octant,
+}
+
+impl BlockLayout {
+    pub(crate) fn height(self) -> u32 {
+        match self {
+            BlockLayout::Quadrant => 2,
+            BlockLayout::Sextant => 3,
+            BlockLayout::Octant => 4,
+        }
+    }
+
+    fn get_symbol(self, pattern: u8) -> char {
+        match self {
+            BlockLayout::Quadrant => QUADRANTS[pattern as usize],
+            BlockLayout::Sextant => get_sextant(pattern),
+            BlockLayout::Octant => get_octant(pattern),
+        }
+    }
+}
+
+/// Finds the layout and the pattern of sub-pixels a block element symbol is drawn with,
+/// the pattern of a layout with fewer sub-pixels is preferred
+pub(crate) fn get_block_shape(symbol: char) -> Option<(BlockLayout, u8)> {
+    [
+        BlockLayout::Quadrant,
+        BlockLayout::Sextant,
+        BlockLayout::Octant,
+    ]
+    .into_iter()
+    .find_map(|layout| {
+        let patterns = 1u16 << (BLOCK_WIDTH * layout.height());
+        (0..patterns)
+            .map(|pattern| pattern as u8)
+            .find(|pattern| layout.get_symbol(*pattern) == symbol)
+            .map(|pattern| (layout, pattern))
+    })
+}
+
+const QUADRANTS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+fn get_sextant(pattern: u8) -> char {
+    // left and right halves, as well as the empty and the full cell, already exist
+    // as regular block elements and are skipped by the sextants block
+    match pattern {
+        0 => ' ',
+        21 => '▌',
+        42 => '▐',
+        63 => '█',
+        _ => {
+            let skipped = (pattern > 21) as u32 + (pattern > 42) as u32;
+            char::from_u32(0x1FB00 + pattern as u32 - 1 - skipped).unwrap_or(' ')
+        }
+    }
+}
+
+/// Octant patterns that are represented by older block elements, sorted by pattern
+const OCTANT_EXCEPTIONS: [(u8, char); 26] = [
+    (0, ' '),
+    (1, '\u{1CEA8}'),
+    (2, '\u{1CEAB}'),
+    (3, '\u{1FB82}'),
+    (5, '▘'),
+    (10, '▝'),
+    (15, '▀'),
+    (20, '\u{1FBE6}'),
+    (40, '\u{1FBE7}'),
+    (63, '\u{1FB85}'),
+    (64, '\u{1CEA3}'),
+    (80, '▖'),
+    (85, '▌'),
+    (90, '▞'),
+    (95, '▛'),
+    (128, '\u{1CEA0}'),
+    (160, '▗'),
+    (165, '▚'),
+    (170, '▐'),
+    (175, '▜'),
+    (192, '▂'),
+    (240, '▄'),
+    (245, '▙'),
+    (250, '▟'),
+    (252, '▆'),
+    (255, '█'),
+];
+
+fn get_octant(pattern: u8) -> char {
+    match OCTANT_EXCEPTIONS.binary_search_by_key(&pattern, |(exception, _)| *exception) {
+        Ok(idx) => OCTANT_EXCEPTIONS[idx].1,
+        Err(skipped) => char::from_u32(0x1CD00 + pattern as u32 - skipped as u32).unwrap_or(' '),
+    }
+}
+
+/// Turns every 2xN block of pixels into a block element with two colors.
+///
+/// Colorized cells use the partition of the sub-pixels into two groups that keeps
+/// their colors closest to the group averages, the brighter group becomes a foreground.
+/// Without colors the bright pixels are filled.
+pub(crate) fn symbolize_blocks(
+    image: &RgbImage,
+    layout: BlockLayout,
+    colorize: bool,
+) -> Vec<Vec<Cell>> {
+    let height = layout.height();
+
+    let mut result = vec![];
+    for row in 0..image.height() / height {
+        let mut result_row = vec![];
+        for column in 0..image.width() / BLOCK_WIDTH {
+            let mut pixels = vec![];
+            for y in 0..height {
+                for x in 0..BLOCK_WIDTH {
+                    pixels.push(*image.get_pixel(column * BLOCK_WIDTH + x, row * height + y));
+                }
+            }
+
+            let pattern = if colorize {
+                get_best_partition(&pixels)
+            } else {
+                pixels
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, pixel)| is_bright(pixel))
+                    .fold(0, |pattern, (idx, _)| pattern | 1 << idx)
+            };
+            result_row.push(get_cell(&pixels, pattern, layout));
+        }
+
+        result.push(result_row);
+    }
+
+    result
+}
+
+/// Finds the pattern of the sub-pixels that splits them into two groups with the least color error.
+/// The full pattern is returned when splitting doesn't make it better.
+fn get_best_partition(pixels: &[Rgb<u8>]) -> u8 {
+    let full_pattern = ((1u16 << pixels.len()) - 1) as u8;
+    let channels: Vec<[f32; 3]> = pixels
+        .iter()
+        .map(|pixel| pixel.0.map(|c| c as f32))
+        .collect();
+    let total = channels.iter().fold([0.0; 3], |sum, c| add(&sum, c));
+
+    // minimizing the squared error of a partition is the same as maximizing
+    // the sum of squared group sums divided by group sizes
+    let score =
+        |sum: &[f32; 3], count: usize| sum.iter().map(|c| c * c).sum::<f32>() / count as f32;
+    let mut best_pattern = full_pattern;
+    let mut best_score = score(&total, pixels.len()) + 1e-3;
+
+    for pattern in 1..full_pattern {
+        let (mut sum, mut count) = ([0.0; 3], 0);
+        for (idx, channel) in channels.iter().enumerate() {
+            if pattern & 1 << idx != 0 {
+                sum = add(&sum, channel);
+                count += 1;
+            }
+        }
+
+        let rest = [total[0] - sum[0], total[1] - sum[1], total[2] - sum[2]];
+        let pattern_score = score(&sum, count) + score(&rest, pixels.len() - count);
+        if pattern_score > best_score {
+            best_pattern = pattern;
+            best_score = pattern_score;
+        }
+    }
+
+    best_pattern
+}
+
+fn add(first: &[f32; 3], second: &[f32; 3]) -> [f32; 3] {
+    [
+        first[0] + second[0],
+        first[1] + second[1],
+        first[2] + second[2],
+    ]
+}
+
+/// Builds a cell for the pattern, making sure the brighter group of sub-pixels is a foreground
+fn get_cell(pixels: &[Rgb<u8>], pattern: u8, layout: BlockLayout) -> Cell {
+    let full_pattern = ((1u16 << pixels.len()) - 1) as u8;
+    let (mut on, mut off) = (vec![], vec![]);
+    for (idx, pixel) in pixels.iter().enumerate() {
+        if pattern & 1 << idx != 0 {
+            on.push(*pixel);
+        } else {
+            off.push(*pixel);
+        }
+    }
+
+    let (mut pattern, mut fg, mut bg) = (pattern, get_average_pixel(&on), get_average_pixel(&off));
+    if pattern == 0 {
+        // nothing is bright enough, so the cell is empty and the colors are the same
+        fg = bg;
+    } else if pattern == full_pattern {
+        bg = fg;
+    } else if get_luminance(&fg) < get_luminance(&bg) {
+        pattern = !pattern & full_pattern;
+        (fg, bg) = (bg, fg);
+    }
+
+    Cell::new(layout.get_symbol(pattern), fg, Some(bg))
+}
+
+fn is_bright(pixel: &Rgb<u8>) -> bool {
+    get_luminance(pixel) >= MONOCHROME_THRESHOLD
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+
+    use std::collections::HashSet;
+
+    use super::{
+        get_block_shape, get_octant, get_sextant, symbolize_blocks, symbolize_half_blocks,
+        BlockLayout,
+    };
+    use crate::Cell;
+
+    fn get_image() -> RgbImage {
+        RgbImage::from_fn(2, 2, |x, y| match (x, y) {
+            (0, 0) => Rgb([255, 0, 0]),
+            (0, 1) => Rgb([0, 0, 255]),
+            (1, 0) => Rgb([0, 0, 0]),
+            _ => Rgb([255, 255, 255]),
+        })
+    }
+
+    #[test]
+    fn uses_top_pixel_as_foreground_and_bottom_as_background() {
+        let result = symbolize_half_blocks(&get_image(), true);
+
+        assert_eq!(
+            result,
+            vec![vec![
+                Cell::new('▀', Rgb([255, 0, 0]), Some(Rgb([0, 0, 255]))),
+                Cell::new('▀', Rgb([0, 0, 0]), Some(Rgb([255, 255, 255]))),
+            ]]
+        );
+    }
+
+    #[test]
+    fn fills_bright_pixels_without_colors() {
+        let symbols: Vec<char> = symbolize_half_blocks(&get_image(), false)[0]
+            .iter()
+            .map(|cell| cell.symbol)
+            .collect();
+
+        assert_eq!(symbols, vec![' ', '▄']);
+    }
+
+    #[test]
+    fn finds_shape_of_block_symbol() {
+        assert_eq!(get_block_shape('▀'), Some((BlockLayout::Quadrant, 3)));
+        assert_eq!(get_block_shape(' '), Some((BlockLayout::Quadrant, 0)));
+        assert_eq!(
+            get_block_shape('\u{1FB00}'),
+            Some((BlockLayout::Sextant, 1))
+        );
+        assert_eq!(get_block_shape('▂'), Some((BlockLayout::Octant, 192)));
+        assert_eq!(get_block_shape('@'), None);
+    }
+
+    #[test]
+    fn maps_every_sextant_pattern_to_unique_symbol() {
+        let symbols: HashSet<char> = (0..64).map(get_sextant).collect();
+
+        assert_eq!(symbols.len(), 64);
+        assert_eq!(get_sextant(1), '\u{1FB00}');
+        assert_eq!(get_sextant(22), '\u{1FB14}');
+        assert_eq!(get_sextant(62), '\u{1FB3B}');
+    }
+
+    #[test]
+    fn maps_every_octant_pattern_to_unique_symbol() {
+        let symbols: HashSet<char> = (0..=255).map(get_octant).collect();
+
+        assert_eq!(symbols.len(), 256);
+        assert_eq!(get_octant(4), '\u{1CD00}');
+        assert_eq!(get_octant(6), '\u{1CD01}');
+        assert_eq!(get_octant(254), '\u{1CDE5}');
+    }
+
+    #[test]
+    fn splits_block_into_two_colors_with_brighter_foreground() {
+        let image = RgbImage::from_fn(2, 2, |x, _| {
+            if x == 0 {
+                Rgb([255, 0, 0])
+            } else {
+                Rgb([0, 0, 255])
+            }
+        });
+        let result = symbolize_blocks(&image, BlockLayout::Quadrant, true);
+
+        assert_eq!(
+            result,
+            vec![vec![Cell::new(
+                '▌',
+                Rgb([255, 0, 0]),
+                Some(Rgb([0, 0, 255]))
+            )]]
+        );
+    }
+
+    #[test]
+    fn fills_uniform_block() {
+        let image = RgbImage::from_pixel(2, 3, Rgb([10, 20, 30]));
+        let result = symbolize_blocks(&image, BlockLayout::Sextant, true);
+
+        assert_eq!(
+            result,
+            vec![vec![Cell::new(
+                '█',
+                Rgb([10, 20, 30]),
+                Some(Rgb([10, 20, 30]))
+            )]]
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use image::RgbImage;
+
+use crate::{dither, get_average_pixel, get_luminance, Cell, Dither};
+
+/// Options of the [`crate::Mode::Braille`] rendering mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BrailleOptions {
+    /// Luminance (0-255) starting from which a sub-pixel is drawn as a raised dot
+    pub threshold: u8,
+    /// Raise dots for the pixels darker than the threshold instead of the brighter ones
+    pub invert: bool,
+    /// Defines how the thresholding error is spread instead of being cut off
+    pub dither: Dither,
+}
+
+impl Default for BrailleOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 128,
+            invert: false,
+            dither: Dither::None,
+        }
+    }
+}
+
+/// Width of a single braille glyph in dots
+pub(crate) const CELL_WIDTH: u32 = 2;
+/// Height of a single braille glyph in dots
+pub(crate) const CELL_HEIGHT: u32 = 4;
+
+pub(crate) const BRAILLE_BASE: u32 = 0x2800;
+
+/// Bits of the braille glyph offset by dot position, indexed as `[y][x]`
+pub(crate) const DOT_BITS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Packs every 2x4 block of the image into a single braille glyph.
+/// Every glyph is colored with the average color of its raised dots.
+pub(crate) fn symbolize(image: &RgbImage, options: BrailleOptions) -> Vec<Vec<Cell>> {
+    let dots = get_dots(image, options);
+    let (columns, rows) = (image.width() / CELL_WIDTH, image.height() / CELL_HEIGHT);
+
+    let mut result = vec![];
+    for row in 0..rows {
+        let mut result_row = vec![];
+        for column in 0..columns {
+            let mut bits = 0;
+            let mut raised = vec![];
+            let mut all = vec![];
+            for (dy, dot_bits_row) in DOT_BITS.iter().enumerate() {
+                for (dx, dot_bit) in dot_bits_row.iter().enumerate() {
+                    let (x, y) = (
+                        column * CELL_WIDTH + dx as u32,
+                        row * CELL_HEIGHT + dy as u32,
+                    );
+                    let pixel = *image.get_pixel(x, y);
+                    if dots[(y * image.width() + x) as usize] {
+                        bits |= dot_bit;
+                        raised.push(pixel);
+                    }
+                    all.push(pixel);
+                }
+            }
+
+            let color = if raised.is_empty() {
+                get_average_pixel(&all)
+            } else {
+                get_average_pixel(&raised)
+            };
+            let symbol = char::from_u32(BRAILLE_BASE + bits).unwrap_or(' ');
+            result_row.push(Cell::new(symbol, color, None));
+        }
+
+        result.push(result_row);
+    }
+
+    result
+}
+
+/// Decides which sub-pixels of the image are raised dots
+fn get_dots(image: &RgbImage, options: BrailleOptions) -> Vec<bool> {
+    let luminances: Vec<[f32; 1]> = image.pixels().map(|pixel| [get_luminance(pixel)]).collect();
+    let threshold = options.threshold as f32;
+
+    dither::dither(
+        luminances,
+        image.width() as usize,
+        options.dither,
+        [255.0],
+        |luminance| {
+            if luminance[0] >= threshold {
+                (1, [255.0])
+            } else {
+                (0, [0.0])
+            }
+        },
+    )
+    .into_iter()
+    .map(|is_bright| (is_bright == 1) != options.invert)
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+
+    use super::{symbolize, BrailleOptions};
+    use crate::{Cell, Dither};
+
+    fn get_half_lit_image() -> RgbImage {
+        RgbImage::from_fn(2, 4, |x, _| {
+            if x == 0 {
+                Rgb([255, 255, 255])
+            } else {
+                Rgb([0, 0, 0])
+            }
+        })
+    }
+
+    #[test]
+    fn packs_block_into_braille_glyph() {
+        let result = symbolize(&get_half_lit_image(), BrailleOptions::default());
+
+        assert_eq!(
+            result,
+            vec![vec![Cell::new('⡇', Rgb([255, 255, 255]), None)]]
+        );
+    }
+
+    #[test]
+    fn inverts_dots() {
+        let result = symbolize(
+            &get_half_lit_image(),
+            BrailleOptions {
+                invert: true,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(result, vec![vec![Cell::new('⢸', Rgb([0, 0, 0]), None)]]);
+    }
+
+    #[test]
+    fn dithers_flat_gray_into_pattern() {
+        let image = RgbImage::from_pixel(2, 4, Rgb([128, 128, 128]));
+        let result = symbolize(
+            &image,
+            BrailleOptions {
+                dither: Dither::FloydSteinberg,
+                ..Default::default()
+            },
+        );
+        let raised_dots = (result[0][0].symbol as u32 - 0x2800).count_ones();
+
+        assert!(raised_dots > 0 && raised_dots < 8);
+    }
+}
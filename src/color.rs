@@ -0,0 +1,203 @@
+use image::Rgb;
+
+/// Defines how the difference between two colors is measured when a pixel is matched with a palette color
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DistanceMetric {
+    /// Sum of absolute RGB channel differences
+    #[default]
+    Manhattan,
+    /// Straight-line distance in RGB
+    Euclidean,
+    /// Euclidean RGB distance with channel weights depending on the mean red value,
+    /// a cheap approximation of the perceived difference
+    Redmean,
+    /// Euclidean distance in CIELAB (ΔE*76)
+    Cie76,
+    /// CIEDE2000 color difference (ΔE*00), the most accurate and the slowest one
+    Ciede2000,
+}
+
+impl DistanceMetric {
+    /// Difference between two colors, the less it is the more similar the colors are
+    pub fn distance(&self, first: &Rgb<u8>, second: &Rgb<u8>) -> f32 {
+        self.distance_in_space(&self.to_space(first), &self.to_space(second))
+    }
+
+    /// Converts the pixel to the color space the metric works in: RGB or CIELAB
+    pub(crate) fn to_space(self, pixel: &Rgb<u8>) -> [f32; 3] {
+        match self {
+            DistanceMetric::Cie76 | DistanceMetric::Ciede2000 => to_lab(pixel),
+            _ => pixel.0.map(|channel| channel as f32),
+        }
+    }
+
+    /// Same as [`DistanceMetric::distance`], but for the colors already converted with [`DistanceMetric::to_space`]
+    pub(crate) fn distance_in_space(self, first: &[f32; 3], second: &[f32; 3]) -> f32 {
+        let (d0, d1, d2) = (
+            first[0] - second[0],
+            first[1] - second[1],
+            first[2] - second[2],
+        );
+
+        match self {
+            DistanceMetric::Manhattan => d0.abs() + d1.abs() + d2.abs(),
+            DistanceMetric::Euclidean | DistanceMetric::Cie76 => {
+                (d0 * d0 + d1 * d1 + d2 * d2).sqrt()
+            }
+            DistanceMetric::Redmean => {
+                let red_mean = (first[0] + second[0]) / 2.0;
+                ((2.0 + red_mean / 256.0) * d0 * d0
+                    + 4.0 * d1 * d1
+                    + (2.0 + (255.0 - red_mean) / 256.0) * d2 * d2)
+                    .sqrt()
+            }
+            DistanceMetric::Ciede2000 => get_ciede2000(first, second),
+        }
+    }
+}
+
+/// Converts an sRGB channel to the linear light intensity (0.0-1.0)
+pub(crate) fn to_linear(channel: u8) -> f32 {
+    let channel = channel as f32 / 255.0;
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts an sRGB pixel to CIELAB with D65 white point
+pub(crate) fn to_lab(pixel: &Rgb<u8>) -> [f32; 3] {
+    let [r, g, b] = pixel.0.map(to_linear);
+    let (x, y, z) = (
+        (0.4124564 * r + 0.3575761 * g + 0.1804375 * b) / 0.95047,
+        0.2126729 * r + 0.7151522 * g + 0.072175 * b,
+        (0.0193339 * r + 0.119192 * g + 0.9503041 * b) / 1.08883,
+    );
+
+    let f = |t: f32| {
+        if t > 216.0 / 24389.0 {
+            t.cbrt()
+        } else {
+            (24389.0 / 27.0 * t + 16.0) / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+fn get_ciede2000(first: &[f32; 3], second: &[f32; 3]) -> f32 {
+    let [l1, a1, b1] = first.map(|c| c as f64);
+    let [l2, a2, b2] = second.map(|c| c as f64);
+
+    let c_mean = ((a1.hypot(b1)) + (a2.hypot(b2))) / 2.0;
+    let g = 0.5 * (1.0 - (c_mean.powi(7) / (c_mean.powi(7) + 25f64.powi(7))).sqrt());
+    let (a1, a2) = (a1 * (1.0 + g), a2 * (1.0 + g));
+    let (c1, c2) = (a1.hypot(b1), a2.hypot(b2));
+    let hue = |a: f64, b: f64| {
+        if a == 0.0 && b == 0.0 {
+            0.0
+        } else {
+            b.atan2(a).to_degrees().rem_euclid(360.0)
+        }
+    };
+    let (h1, h2) = (hue(a1, b1), hue(a2, b2));
+
+    let delta_l = l2 - l1;
+    let delta_c = c2 - c1;
+    let delta_h = if c1 * c2 == 0.0 {
+        0.0
+    } else if (h2 - h1).abs() <= 180.0 {
+        h2 - h1
+    } else if h2 - h1 > 180.0 {
+        h2 - h1 - 360.0
+    } else {
+        h2 - h1 + 360.0
+    };
+    let delta_h = 2.0 * (c1 * c2).sqrt() * (delta_h / 2.0).to_radians().sin();
+
+    let l_mean = (l1 + l2) / 2.0;
+    let c_mean = (c1 + c2) / 2.0;
+    let h_mean = if c1 * c2 == 0.0 {
+        h1 + h2
+    } else if (h1 - h2).abs() <= 180.0 {
+        (h1 + h2) / 2.0
+    } else if h1 + h2 < 360.0 {
+        (h1 + h2 + 360.0) / 2.0
+    } else {
+        (h1 + h2 - 360.0) / 2.0
+    };
+
+    let t = 1.0 - 0.17 * (h_mean - 30.0).to_radians().cos()
+        + 0.24 * (2.0 * h_mean).to_radians().cos()
+        + 0.32 * (3.0 * h_mean + 6.0).to_radians().cos()
+        - 0.20 * (4.0 * h_mean - 63.0).to_radians().cos();
+    let delta_theta = 30.0 * (-((h_mean - 275.0) / 25.0).powi(2)).exp();
+    let r_c = 2.0 * (c_mean.powi(7) / (c_mean.powi(7) + 25f64.powi(7))).sqrt();
+    let s_l = 1.0 + 0.015 * (l_mean - 50.0).powi(2) / (20.0 + (l_mean - 50.0).powi(2)).sqrt();
+    let s_c = 1.0 + 0.045 * c_mean;
+    let s_h = 1.0 + 0.015 * c_mean * t;
+    let r_t = -(2.0 * delta_theta).to_radians().sin() * r_c;
+
+    ((delta_l / s_l).powi(2)
+        + (delta_c / s_c).powi(2)
+        + (delta_h / s_h).powi(2)
+        + r_t * (delta_c / s_c) * (delta_h / s_h))
+        .sqrt() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgb;
+
+    use super::{get_ciede2000, to_lab, DistanceMetric};
+
+    #[test]
+    fn converts_to_lab() {
+        let white = to_lab(&Rgb([255, 255, 255]));
+        let red = to_lab(&Rgb([255, 0, 0]));
+
+        assert!((white[0] - 100.0).abs() < 0.01 && white[1].abs() < 0.01 && white[2].abs() < 0.01);
+        assert!((red[0] - 53.24).abs() < 0.05);
+        assert!((red[1] - 80.09).abs() < 0.05);
+        assert!((red[2] - 67.20).abs() < 0.05);
+    }
+
+    #[test]
+    fn computes_ciede2000_reference_pairs() {
+        // reference data by Sharma, Wu and Dalal
+        let pairs = [
+            ([50.0, 2.6772, -79.7751], [50.0, 0.0, -82.7485], 2.0425),
+            ([50.0, -1.3802, -84.2814], [50.0, 0.0, -82.7485], 1.0),
+            ([50.0, 2.5, 0.0], [73.0, 25.0, -18.0], 27.1492),
+            (
+                [60.2574, -34.0099, 36.2677],
+                [60.4626, -34.1751, 39.4387],
+                1.2644,
+            ),
+            (
+                [22.7233, 20.0904, -46.694],
+                [23.0331, 14.973, -42.5619],
+                2.0373,
+            ),
+        ];
+
+        for (first, second, expected) in pairs {
+            assert!((get_ciede2000(&first, &second) - expected).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn perceptual_metrics_dont_match_dark_green_with_black() {
+        let (dark_green, black, green) = (Rgb([0, 120, 0]), Rgb([0, 0, 0]), Rgb([0, 200, 120]));
+
+        assert!(
+            DistanceMetric::Manhattan.distance(&dark_green, &black)
+                < DistanceMetric::Manhattan.distance(&dark_green, &green)
+        );
+        for metric in [DistanceMetric::Cie76, DistanceMetric::Ciede2000] {
+            assert!(metric.distance(&dark_green, &green) < metric.distance(&dark_green, &black));
+        }
+    }
+}
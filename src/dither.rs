@@ -0,0 +1,237 @@
+/// Defines how the error of matching a pixel with the nearest available color is spread over the image
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Dither {
+    /// Every pixel is matched on its own
+    #[default]
+    None,
+    /// Error diffusion to 4 neighbours, the classic one
+    FloydSteinberg,
+    /// Error diffusion to 6 neighbours losing a quarter of the error, gives more contrast
+    Atkinson,
+    /// Error diffusion to 12 neighbours over three rows (Jarvis, Judice and Ninke), gives smoother gradients
+    JarvisJudiceNinke,
+    /// Error diffusion to 10 neighbours over three rows, close to Jarvis-Judice-Ninke but a bit sharper
+    Sierra,
+    /// Ordered dithering with a 2x2 Bayer matrix
+    Bayer2,
+    /// Ordered dithering with a 4x4 Bayer matrix
+    Bayer4,
+    /// Ordered dithering with an 8x8 Bayer matrix
+    Bayer8,
+}
+
+/// Neighbours the error is diffused to as `(dx, dy, weight)` and the sum all weights are divided by
+type Kernel = (&'static [(isize, usize, f32)], f32);
+
+const FLOYD_STEINBERG: Kernel = (&[(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)], 16.0);
+
+const ATKINSON: Kernel = (
+    &[
+        (1, 0, 1.0),
+        (2, 0, 1.0),
+        (-1, 1, 1.0),
+        (0, 1, 1.0),
+        (1, 1, 1.0),
+        (0, 2, 1.0),
+    ],
+    8.0,
+);
+
+const JARVIS_JUDICE_NINKE: Kernel = (
+    &[
+        (1, 0, 7.0),
+        (2, 0, 5.0),
+        (-2, 1, 3.0),
+        (-1, 1, 5.0),
+        (0, 1, 7.0),
+        (1, 1, 5.0),
+        (2, 1, 3.0),
+        (-2, 2, 1.0),
+        (-1, 2, 3.0),
+        (0, 2, 5.0),
+        (1, 2, 3.0),
+        (2, 2, 1.0),
+    ],
+    48.0,
+);
+
+const SIERRA: Kernel = (
+    &[
+        (1, 0, 5.0),
+        (2, 0, 3.0),
+        (-2, 1, 2.0),
+        (-1, 1, 4.0),
+        (0, 1, 5.0),
+        (1, 1, 4.0),
+        (2, 1, 2.0),
+        (-1, 2, 2.0),
+        (0, 2, 3.0),
+        (1, 2, 2.0),
+    ],
+    32.0,
+);
+
+impl Dither {
+    fn get_kernel(self) -> Option<Kernel> {
+        match self {
+            Dither::FloydSteinberg => Some(FLOYD_STEINBERG),
+            Dither::Atkinson => Some(ATKINSON),
+            Dither::JarvisJudiceNinke => Some(JARVIS_JUDICE_NINKE),
+            Dither::Sierra => Some(SIERRA),
+            _ => None,
+        }
+    }
+
+    fn get_bayer_size(self) -> Option<usize> {
+        match self {
+            Dither::Bayer2 => Some(2),
+            Dither::Bayer4 => Some(4),
+            Dither::Bayer8 => Some(8),
+            _ => None,
+        }
+    }
+}
+
+/// Matches every value of the image (given row by row) with one of the available levels.
+///
+/// `nearest` returns the index of the level the value is matched with and the level itself,
+/// `spread` is the distance between two neighbouring levels per channel, it scales the ordered dithering thresholds.
+/// Returns the matched level index of every value.
+pub(crate) fn dither<const N: usize>(
+    mut values: Vec<[f32; N]>,
+    width: usize,
+    dither: Dither,
+    spread: [f32; N],
+    nearest: impl Fn(&[f32; N]) -> (usize, [f32; N]),
+) -> Vec<usize> {
+    if width == 0 {
+        return vec![];
+    }
+
+    let height = values.len() / width;
+    let kernel = dither.get_kernel();
+    let bayer = dither
+        .get_bayer_size()
+        .map(|size| (size, get_bayer_matrix(size)));
+
+    let mut result = Vec::with_capacity(values.len());
+    for y in 0..height {
+        for x in 0..width {
+            let mut value = values[y * width + x];
+            if let Some((size, matrix)) = &bayer {
+                let threshold = matrix[(y % size) * size + x % size];
+                for (channel, channel_spread) in value.iter_mut().zip(spread) {
+                    *channel += threshold * channel_spread;
+                }
+            }
+
+            let (idx, level) = nearest(&value);
+            result.push(idx);
+
+            if let Some((neighbours, divisor)) = kernel {
+                let mut error = value;
+                for (channel, level) in error.iter_mut().zip(level) {
+                    *channel -= level;
+                }
+
+                for (dx, dy, weight) in neighbours {
+                    let (nx, ny) = (x as isize + dx, y + dy);
+                    if nx >= 0 && (nx as usize) < width && ny < height {
+                        let neighbour = &mut values[ny * width + nx as usize];
+                        for (channel, error) in neighbour.iter_mut().zip(error) {
+                            *channel += error * weight / divisor;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// Bayer threshold matrix of the given size (a power of two) row by row, thresholds are in -0.5..0.5 range
+fn get_bayer_matrix(size: usize) -> Vec<f32> {
+    let mut matrix = vec![0usize];
+    let mut current_size = 1;
+    while current_size < size {
+        let next_size = current_size * 2;
+        let mut next = vec![0; next_size * next_size];
+        for y in 0..next_size {
+            for x in 0..next_size {
+                let base = 4 * matrix[(y % current_size) * current_size + x % current_size];
+                let offset = match (x / current_size, y / current_size) {
+                    (0, 0) => 0,
+                    (1, 0) => 2,
+                    (0, _) => 3,
+                    _ => 1,
+                };
+                next[y * next_size + x] = base + offset;
+            }
+        }
+
+        matrix = next;
+        current_size = next_size;
+    }
+
+    let count = (size * size) as f32;
+    matrix
+        .into_iter()
+        .map(|value| (value as f32 + 0.5) / count - 0.5)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dither, get_bayer_matrix, Dither};
+
+    fn dither_gray(value: f32, dither_type: Dither) -> Vec<usize> {
+        dither(vec![[value]; 256], 16, dither_type, [255.0], |value| {
+            if value[0] >= 128.0 {
+                (1, [255.0])
+            } else {
+                (0, [0.0])
+            }
+        })
+    }
+
+    #[test]
+    fn builds_bayer_matrix() {
+        let matrix: Vec<f32> = get_bayer_matrix(4)
+            .into_iter()
+            .map(|threshold| (threshold + 0.5) * 16.0 - 0.5)
+            .collect();
+
+        assert_eq!(
+            matrix,
+            vec![
+                0.0, 8.0, 2.0, 10.0, //
+                12.0, 4.0, 14.0, 6.0, //
+                3.0, 11.0, 1.0, 9.0, //
+                15.0, 7.0, 13.0, 5.0, //
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_average_of_flat_gray() {
+        for dither_type in [
+            Dither::FloydSteinberg,
+            Dither::JarvisJudiceNinke,
+            Dither::Sierra,
+            Dither::Bayer2,
+            Dither::Bayer4,
+            Dither::Bayer8,
+        ] {
+            let lit = dither_gray(64.0, dither_type).into_iter().sum::<usize>();
+
+            // a quarter of 256 values, give or take the edge effects of error diffusion
+            assert!((54..=74).contains(&lit), "{:?}: {}", dither_type, lit);
+        }
+    }
+
+    #[test]
+    fn doesnt_dither_without_algorithm() {
+        assert_eq!(dither_gray(100.0, Dither::None), vec![0; 256]);
+    }
+}
@@ -0,0 +1,96 @@
+use std::{error::Error, fmt, io};
+
+use image::ImageError;
+
+/// Everything that can go wrong while symbolizing an image
+#[derive(Debug)]
+pub enum SymbolizeError {
+    /// The mode draws with the palette symbols, but the palette is empty
+    EmptyPalette,
+    /// The scale isn't a positive finite number
+    InvalidScale(f32),
+    /// The cell aspect ratio isn't a positive number
+    InvalidCellAspectRatio(f32),
+    /// The image is scaled down to nothing, there are no columns or no rows to draw
+    ZeroSizedOutput,
+    /// The drawn output has more pixels than an image can hold
+    OversizedOutput,
+    /// The mode isn't known, e.g. a misspelled one passed from the command line
+    UnsupportedMode(String),
+    /// The value of the option isn't known or can't be parsed
+    InvalidOption {
+        /// Name of the option
+        name: &'static str,
+        /// Value that is rejected
+        value: String,
+    },
+    /// The option required by the mode isn't set
+    MissingOption(&'static str),
+    /// The font file can't be parsed, the reason is given
+    InvalidFont(&'static str),
+    /// The font doesn't contain any of the palette symbols
+    MissingGlyphs,
+    /// The image can't be decoded
+    Decode(ImageError),
+    /// The image can't be encoded, e.g. its format isn't supported
+    Encode(ImageError),
+    /// The image, the font or the output can't be read or written
+    Io(io::Error),
+}
+
+impl fmt::Display for SymbolizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolizeError::EmptyPalette => {
+                write!(f, "palette should contain at least one symbol")
+            }
+            SymbolizeError::InvalidScale(scale) => {
+                write!(f, "scale should be > 0, got {}", scale)
+            }
+            SymbolizeError::InvalidCellAspectRatio(ratio) => {
+                write!(f, "cell aspect ratio should be > 0, got {}", ratio)
+            }
+            SymbolizeError::ZeroSizedOutput => {
+                write!(f, "the image is scaled down to nothing")
+            }
+            SymbolizeError::OversizedOutput => write!(f, "the output is too large to draw"),
+            SymbolizeError::UnsupportedMode(mode) => write!(f, "unknown mode {}", mode),
+            SymbolizeError::InvalidOption { name, value } => {
+                write!(f, "invalid {} {}", name, value)
+            }
+            SymbolizeError::MissingOption(name) => write!(f, "missing {}", name),
+            SymbolizeError::InvalidFont(reason) => write!(f, "{}", reason),
+            SymbolizeError::MissingGlyphs => {
+                write!(f, "font doesn't contain any of the palette symbols")
+            }
+            SymbolizeError::Decode(e) => write!(f, "can't decode the image: {}", e),
+            SymbolizeError::Encode(e) => write!(f, "can't encode the image: {}", e),
+            SymbolizeError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for SymbolizeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SymbolizeError::Decode(e) | SymbolizeError::Encode(e) => Some(e),
+            SymbolizeError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ImageError> for SymbolizeError {
+    fn from(e: ImageError) -> Self {
+        match e {
+            ImageError::IoError(e) => SymbolizeError::Io(e),
+            e => SymbolizeError::Decode(e),
+        }
+    }
+}
+
+impl From<io::Error> for SymbolizeError {
+    fn from(e: io::Error) -> Self {
+        SymbolizeError::Io(e)
+    }
+}
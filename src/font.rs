@@ -0,0 +1,449 @@
+use std::{collections::HashMap, fs, path::Path, sync::OnceLock};
+
+use ab_glyph::{point, Font as _, FontArc, PxScale, ScaleFont};
+use image::{
+    imageops::{resize, FilterType},
+    GrayImage, Luma,
+};
+
+use crate::SymbolizeError;
+
+const INVALID_BDF: SymbolizeError = SymbolizeError::InvalidFont("malformed BDF font");
+const INVALID_PSF: SymbolizeError = SymbolizeError::InvalidFont("malformed PSF font");
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+
+/// Built-in 8x16 bitmap font covering ASCII and Latin-1, rasterized from DejaVu Sans Mono
+const BUILTIN_FONT: &[u8] = include_bytes!("../assets/symbolize-8x16.bdf");
+
+static BUILTIN: OnceLock<Font> = OnceLock::new();
+
+/// Monospace font used to rasterize symbols, either an outline (TTF/OTF) or a bitmap (BDF/PSF) one
+#[derive(Debug, Clone)]
+pub struct Font(FontKind);
+
+#[derive(Debug, Clone)]
+enum FontKind {
+    Outline(FontArc),
+    Bitmap(BitmapFont),
+}
+
+impl Font {
+    /// Loads a font from the file. BDF and PSF fonts are recognized by their header,
+    /// everything else is treated as TTF/OTF.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, SymbolizeError> {
+        Self::from_bytes(fs::read(path)?)
+    }
+
+    /// Same as [`Font::open`], but for the font file that is already read
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, SymbolizeError> {
+        if data.starts_with(b"STARTFONT") {
+            let source = String::from_utf8(data).map_err(|_| INVALID_BDF)?;
+            return Ok(Self(FontKind::Bitmap(BitmapFont::parse(&source)?)));
+        }
+
+        if data.starts_with(&PSF1_MAGIC) || data.starts_with(&PSF2_MAGIC) {
+            return Ok(Self(FontKind::Bitmap(BitmapFont::parse_psf(&data)?)));
+        }
+
+        match FontArc::try_from_vec(data) {
+            Ok(font) => Ok(Self(FontKind::Outline(font))),
+            Err(_) => Err(SymbolizeError::InvalidFont(
+                "font should be a valid TTF, OTF, BDF or PSF file",
+            )),
+        }
+    }
+
+    /// The built-in 8x16 bitmap font, it covers ASCII and Latin-1.
+    /// It's parsed on the first use and shared afterwards
+    pub fn builtin() -> &'static Self {
+        BUILTIN.get_or_init(|| {
+            Self::from_bytes(BUILTIN_FONT.to_vec()).expect("the built-in font should be valid")
+        })
+    }
+
+    /// Draws the symbol filling a cell of the given size.
+    /// Returns coverage of every pixel of the cell (0.0-1.0) row by row,
+    /// or [`None`] if the font doesn't have the symbol.
+    pub(crate) fn rasterize(&self, symbol: char, width: u32, height: u32) -> Option<Vec<f32>> {
+        match &self.0 {
+            FontKind::Outline(font) => rasterize_outline(font, symbol, width, height),
+            FontKind::Bitmap(font) => font.rasterize(symbol, width, height),
+        }
+    }
+}
+
+fn rasterize_outline(font: &FontArc, symbol: char, width: u32, height: u32) -> Option<Vec<f32>> {
+    let id = font.glyph_id(symbol);
+    if id.0 == 0 {
+        return None;
+    }
+
+    let mut coverage = vec![0.0; (width * height) as usize];
+    if symbol.is_whitespace() {
+        return Some(coverage);
+    }
+
+    // the line height is stretched to the cell height and the advance to the cell width
+    let (advance, line_height) = (font.h_advance_unscaled(id), font.height_unscaled());
+    let scale = PxScale {
+        x: if advance > 0.0 {
+            width as f32 * line_height / advance
+        } else {
+            height as f32
+        },
+        y: height as f32,
+    };
+    let ascent = font.as_scaled(scale).ascent();
+    let outlined = font.outline_glyph(id.with_scale_and_position(scale, point(0.0, ascent)))?;
+
+    let bounds = outlined.px_bounds();
+    outlined.draw(|x, y, glyph_coverage| {
+        let (x, y) = (
+            bounds.min.x as i32 + x as i32,
+            bounds.min.y as i32 + y as i32,
+        );
+        if x >= 0 && y >= 0 && (x as u32) < width && (y as u32) < height {
+            coverage[(y as u32 * width + x as u32) as usize] = glyph_coverage.min(1.0);
+        }
+    });
+
+    Some(coverage)
+}
+
+/// Glyph bitmaps of a BDF font, each drawn into the font bounding box
+#[derive(Debug, Clone)]
+struct BitmapFont {
+    width: u32,
+    height: u32,
+    glyphs: HashMap<char, GrayImage>,
+}
+
+impl BitmapFont {
+    fn parse(source: &str) -> Result<Self, SymbolizeError> {
+        let invalid = || INVALID_BDF;
+        let numbers = |line: &str| -> Result<Vec<i32>, SymbolizeError> {
+            line.split_whitespace()
+                .skip(1)
+                .map(|value| value.parse().map_err(|_| invalid()))
+                .collect()
+        };
+
+        let mut lines = source.lines().map(str::trim);
+        let (mut width, mut height, mut x_offset, mut y_offset) = (0, 0, 0, 0);
+        let mut glyphs = HashMap::new();
+        let (mut encoding, mut bbx) = (None, None);
+
+        while let Some(line) = lines.next() {
+            let keyword = line.split_whitespace().next().unwrap_or_default();
+            match keyword {
+                "FONTBOUNDINGBOX" => {
+                    let values = numbers(line)?;
+                    if values.len() != 4 || values[0] <= 0 || values[1] <= 0 {
+                        return Err(invalid());
+                    }
+                    (width, height, x_offset, y_offset) =
+                        (values[0], values[1], values[2], values[3]);
+                }
+                "ENCODING" => {
+                    encoding = numbers(line)?
+                        .first()
+                        .and_then(|code| char::from_u32(*code as u32));
+                }
+                "BBX" => {
+                    let values = numbers(line)?;
+                    if values.len() != 4 {
+                        return Err(invalid());
+                    }
+                    bbx = Some((values[0], values[1], values[2], values[3]));
+                }
+                "BITMAP" => {
+                    let (glyph_width, glyph_height, glyph_x_offset, glyph_y_offset) =
+                        bbx.take().ok_or_else(invalid)?;
+                    if width == 0 {
+                        return Err(invalid());
+                    }
+
+                    let mut bitmap = GrayImage::new(width as u32, height as u32);
+                    // rows are counted from the top of the bounding box, whose top is at its ascent,
+                    // the values come from the file, so any of them can overflow
+                    let top = height
+                        .checked_add(y_offset)
+                        .zip(glyph_y_offset.checked_add(glyph_height))
+                        .and_then(|(ascent, glyph_top)| ascent.checked_sub(glyph_top))
+                        .ok_or_else(invalid)?;
+                    let left = glyph_x_offset.checked_sub(x_offset).ok_or_else(invalid)?;
+                    for row in 0..glyph_height {
+                        let hex = lines.next().ok_or_else(invalid)?;
+                        let bits = u64::from_str_radix(hex, 16).map_err(|_| invalid())?;
+                        let row_bits = hex.len() as i32 * 4;
+                        for column in 0..glyph_width.min(row_bits) {
+                            let (Some(x), Some(y)) =
+                                (left.checked_add(column), top.checked_add(row))
+                            else {
+                                return Err(invalid());
+                            };
+                            let is_set = bits >> (row_bits - 1 - column) & 1 == 1;
+                            if is_set && x >= 0 && y >= 0 && x < width && y < height {
+                                bitmap.put_pixel(x as u32, y as u32, Luma([255]));
+                            }
+                        }
+                    }
+
+                    if let Some(symbol) = encoding.take() {
+                        glyphs.insert(symbol, bitmap);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if glyphs.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            width: width as u32,
+            height: height as u32,
+            glyphs,
+        })
+    }
+
+    /// Parses a PSF1 or PSF2 console font. Without a unicode table the glyphs are mapped
+    /// to the code points equal to their indices.
+    fn parse_psf(data: &[u8]) -> Result<Self, SymbolizeError> {
+        let u32_at = |offset: usize| -> Result<u32, SymbolizeError> {
+            data.get(offset..offset + 4)
+                .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .ok_or(INVALID_PSF)
+        };
+
+        // (header size, glyph count, bytes per glyph, width, height, has unicode table)
+        let (header_size, count, glyph_size, width, height, has_table) =
+            if data.starts_with(&PSF1_MAGIC) {
+                let (mode, height) = (
+                    *data.get(2).ok_or(INVALID_PSF)?,
+                    *data.get(3).ok_or(INVALID_PSF)? as usize,
+                );
+                let count = if mode & 0x01 != 0 { 512 } else { 256 };
+                (4, count, height, 8, height, mode & 0x06 != 0)
+            } else {
+                let header_size = u32_at(8)? as usize;
+                let flags = u32_at(12)?;
+                let count = u32_at(16)? as usize;
+                let glyph_size = u32_at(20)? as usize;
+                let (height, width) = (u32_at(24)? as usize, u32_at(28)? as usize);
+                (
+                    header_size,
+                    count,
+                    glyph_size,
+                    width,
+                    height,
+                    flags & 0x01 != 0,
+                )
+            };
+
+        let row_size = width.div_ceil(8);
+        if width == 0
+            || height == 0
+            || row_size
+                .checked_mul(height)
+                .is_none_or(|size| size > glyph_size)
+        {
+            return Err(INVALID_PSF);
+        }
+
+        let glyphs_end = count
+            .checked_mul(glyph_size)
+            .and_then(|size| size.checked_add(header_size))
+            .ok_or(INVALID_PSF)?;
+        let bitmaps: Vec<GrayImage> = data
+            .get(header_size..glyphs_end)
+            .ok_or(INVALID_PSF)?
+            .chunks(glyph_size)
+            .map(|glyph| {
+                GrayImage::from_fn(width as u32, height as u32, |x, y| {
+                    let (x, y) = (x as usize, y as usize);
+                    let is_set = glyph[y * row_size + x / 8] & (0x80 >> (x % 8)) != 0;
+                    Luma([if is_set { 255 } else { 0 }])
+                })
+            })
+            .collect();
+
+        let symbols = if !has_table {
+            (0..count)
+                .map(|idx| char::from_u32(idx as u32).into_iter().collect())
+                .collect()
+        } else if data.starts_with(&PSF1_MAGIC) {
+            get_psf1_symbols(&data[glyphs_end..], count)
+        } else {
+            get_psf2_symbols(&data[glyphs_end..], count)
+        };
+
+        let mut glyphs = HashMap::new();
+        for (bitmap, symbols) in bitmaps.into_iter().zip(symbols) {
+            for symbol in symbols {
+                glyphs.entry(symbol).or_insert_with(|| bitmap.clone());
+            }
+        }
+        if glyphs.is_empty() {
+            return Err(INVALID_PSF);
+        }
+
+        Ok(Self {
+            width: width as u32,
+            height: height as u32,
+            glyphs,
+        })
+    }
+
+    fn rasterize(&self, symbol: char, width: u32, height: u32) -> Option<Vec<f32>> {
+        let bitmap = self.glyphs.get(&symbol)?;
+        let scaled = if (width, height) == (self.width, self.height) {
+            bitmap.clone()
+        } else {
+            resize(bitmap, width, height, FilterType::Triangle)
+        };
+
+        Some(
+            scaled
+                .pixels()
+                .map(|pixel| pixel.0[0] as f32 / 255.0)
+                .collect(),
+        )
+    }
+}
+
+/// Symbols of every glyph from the PSF1 unicode table: UCS-2 values terminated by `0xFFFF`,
+/// multi-symbol sequences after `0xFFFE` are skipped
+fn get_psf1_symbols(table: &[u8], count: usize) -> Vec<Vec<char>> {
+    let mut values = table
+        .chunks_exact(2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]));
+
+    (0..count)
+        .map(|_| {
+            let entry: Vec<u16> = values
+                .by_ref()
+                .take_while(|value| *value != 0xFFFF)
+                .collect();
+            entry
+                .into_iter()
+                .take_while(|value| *value != 0xFFFE)
+                .filter_map(|value| char::from_u32(value as u32))
+                .collect()
+        })
+        .collect()
+}
+
+/// Symbols of every glyph from the PSF2 unicode table: UTF-8 symbols terminated by `0xFF`,
+/// multi-symbol sequences after `0xFE` are skipped
+fn get_psf2_symbols(table: &[u8], count: usize) -> Vec<Vec<char>> {
+    table
+        .split(|byte| *byte == 0xFF)
+        .take(count)
+        .map(|entry| {
+            let single = entry.split(|byte| *byte == 0xFE).next().unwrap_or_default();
+            String::from_utf8_lossy(single)
+                .chars()
+                .filter(|symbol| *symbol != char::REPLACEMENT_CHARACTER)
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Font;
+    use crate::SymbolizeError;
+
+    #[test]
+    fn rasterizes_bdf_glyph() {
+        let font = Font::open("./test-data/glyphs.bdf").unwrap();
+        let coverage = font.rasterize('|', 4, 4).unwrap();
+
+        assert_eq!(
+            coverage,
+            vec![
+                0.0, 1.0, 0.0, 0.0, //
+                0.0, 1.0, 0.0, 0.0, //
+                0.0, 1.0, 0.0, 0.0, //
+                0.0, 1.0, 0.0, 0.0, //
+            ]
+        );
+    }
+
+    #[test]
+    fn returns_none_for_missing_glyph() {
+        let font = Font::open("./test-data/glyphs.bdf").unwrap();
+
+        assert!(font.rasterize('?', 4, 4).is_none());
+    }
+
+    #[test]
+    fn rasterizes_psf_glyphs() {
+        // two 8x2 glyphs, the second one is mapped to 'b' and a sequence of the unicode table
+        let mut psf2 = vec![0x72, 0xb5, 0x4a, 0x86];
+        for value in [0u32, 32, 1, 2, 2, 2, 8] {
+            psf2.extend(value.to_le_bytes());
+        }
+        psf2.extend([0xF0, 0x00, 0x0F, 0xFF]);
+        psf2.extend(b"a\xFFb\xFEcd\xFF");
+        let font = Font::from_bytes(psf2).unwrap();
+
+        assert_eq!(
+            font.rasterize('b', 8, 2).unwrap(),
+            [vec![0.0; 4], vec![1.0; 12]].concat()
+        );
+        assert_eq!(font.rasterize('a', 8, 2).unwrap()[0], 1.0);
+        assert!(font.rasterize('c', 8, 2).is_none());
+
+        // 256 glyphs of 8x1 without a unicode table are mapped by their indices
+        let mut psf1 = vec![0x36, 0x04, 0x00, 0x01];
+        psf1.extend((0..=255).map(|idx| if idx == b'|' { 0x10 } else { 0x00 }));
+        let font = Font::from_bytes(psf1).unwrap();
+
+        assert_eq!(font.rasterize('|', 8, 1).unwrap()[3], 1.0);
+        assert_eq!(font.rasterize('|', 8, 1).unwrap()[4], 0.0);
+    }
+
+    #[test]
+    fn loads_builtin_font() {
+        let font = Font::builtin();
+
+        assert!(font.rasterize('@', 8, 16).is_some());
+        assert!(font.rasterize('é', 8, 16).is_some());
+    }
+
+    #[test]
+    fn returns_error_for_invalid_font() {
+        assert!(matches!(
+            Font::from_bytes(b"definitely not a font".to_vec()),
+            Err(SymbolizeError::InvalidFont(_))
+        ));
+        assert!(matches!(
+            Font::from_bytes(b"STARTFONT 2.1\nENDFONT".to_vec()),
+            Err(SymbolizeError::InvalidFont(_))
+        ));
+    }
+
+    #[test]
+    fn returns_error_for_overflowing_font_metrics() {
+        for bbx in ["BBX 4 4 0 2147483647", "BBX 4 4 -2147483648 0"] {
+            let bdf = format!(
+                "STARTFONT 2.1\nFONTBOUNDINGBOX 4 4 1 0\nENCODING 65\n{}\nBITMAP\n40\nENDFONT",
+                bbx
+            );
+
+            assert!(
+                matches!(
+                    Font::from_bytes(bdf.into_bytes()),
+                    Err(SymbolizeError::InvalidFont(_))
+                ),
+                "{}",
+                bbx
+            );
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use image::{Rgb, RgbImage};
+
+use crate::{get_average_pixel, ramp::get_perceived_lightness, Cell, Font, SymbolizeError};
+
+/// Options of the [`crate::Mode::Glyph`] rendering mode
+#[derive(Debug, Clone)]
+pub struct GlyphOptions {
+    /// Font the palette symbols are rasterized with, ideally the one used to display the result
+    pub font: Font,
+    /// Width of the pixel block matched against a single glyph
+    pub cell_width: u32,
+    /// Height of the pixel block matched against a single glyph
+    pub cell_height: u32,
+    /// Treat dark pixels as ink of a glyph, e.g. for dark text on a light background
+    pub invert: bool,
+    /// Defines how similar a pixel block and a glyph are
+    pub matching: GlyphMatching,
+}
+
+impl GlyphOptions {
+    /// Creates options with 8x16 cells matched by least squares
+    pub fn new(font: Font) -> Self {
+        Self {
+            font,
+            cell_width: 8,
+            cell_height: 16,
+            invert: false,
+            matching: GlyphMatching::default(),
+        }
+    }
+}
+
+/// Similarity measure between a pixel block and a rasterized glyph
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum GlyphMatching {
+    /// The glyph with the least sum of squared lightness differences wins
+    #[default]
+    LeastSquares,
+    /// The glyph with the highest structural similarity (SSIM) wins,
+    /// this favours matching shapes over matching brightness
+    Ssim,
+}
+
+struct RasterizedGlyph {
+    symbol: char,
+    coverage: Vec<f32>,
+}
+
+/// Picks the palette glyph whose rasterized shape is the closest to every cell-sized block of the image.
+/// Every glyph is colored with the coverage-weighted average color of its block.
+pub(crate) fn symbolize(
+    image: &RgbImage,
+    palette: &[char],
+    options: &GlyphOptions,
+) -> Result<Vec<Vec<Cell>>, SymbolizeError> {
+    let (cell_width, cell_height) = (options.cell_width, options.cell_height);
+    if cell_width == 0 || cell_height == 0 {
+        return Err(SymbolizeError::InvalidOption {
+            name: "cell size",
+            value: format!("{}x{}", cell_width, cell_height),
+        });
+    }
+
+    let glyphs: Vec<RasterizedGlyph> = palette
+        .iter()
+        .filter_map(|symbol| {
+            options
+                .font
+                .rasterize(*symbol, cell_width, cell_height)
+                .map(|coverage| RasterizedGlyph {
+                    symbol: *symbol,
+                    coverage,
+                })
+        })
+        .collect();
+
+    if glyphs.is_empty() {
+        return Err(SymbolizeError::MissingGlyphs);
+    }
+
+    let mut result = vec![];
+    for row in 0..image.height() / cell_height {
+        let mut result_row = vec![];
+        for column in 0..image.width() / cell_width {
+            let mut pixels = vec![];
+            for y in 0..cell_height {
+                for x in 0..cell_width {
+                    pixels.push(*image.get_pixel(column * cell_width + x, row * cell_height + y));
+                }
+            }
+
+            let lightness: Vec<f32> = pixels
+                .iter()
+                .map(|pixel| {
+                    let lightness = get_perceived_lightness(pixel);
+                    if options.invert {
+                        1.0 - lightness
+                    } else {
+                        lightness
+                    }
+                })
+                .collect();
+
+            let glyph = get_best_glyph(&glyphs, &lightness, options.matching);
+            let color = get_ink_color(&pixels, &glyph.coverage);
+            result_row.push(Cell::new(glyph.symbol, color, None));
+        }
+
+        result.push(result_row);
+    }
+
+    Ok(result)
+}
+
+fn get_best_glyph<'a>(
+    glyphs: &'a [RasterizedGlyph],
+    lightness: &[f32],
+    matching: GlyphMatching,
+) -> &'a RasterizedGlyph {
+    let mut best = &glyphs[0];
+    let mut best_score = f32::MIN;
+    for glyph in glyphs {
+        let score = match matching {
+            GlyphMatching::LeastSquares => -get_squared_error(lightness, &glyph.coverage),
+            GlyphMatching::Ssim => get_ssim(lightness, &glyph.coverage),
+        };
+
+        if score > best_score {
+            best = glyph;
+            best_score = score;
+        }
+    }
+
+    best
+}
+
+fn get_squared_error(first: &[f32], second: &[f32]) -> f32 {
+    first
+        .iter()
+        .zip(second)
+        .map(|(first, second)| (first - second) * (first - second))
+        .sum()
+}
+
+fn get_ssim(first: &[f32], second: &[f32]) -> f32 {
+    const C1: f32 = 0.01 * 0.01;
+    const C2: f32 = 0.03 * 0.03;
+
+    let count = first.len() as f32;
+    let (first_mean, second_mean) = (
+        first.iter().sum::<f32>() / count,
+        second.iter().sum::<f32>() / count,
+    );
+    let (mut first_variance, mut second_variance, mut covariance) = (0.0, 0.0, 0.0);
+    for (first, second) in first.iter().zip(second) {
+        let (first_delta, second_delta) = (first - first_mean, second - second_mean);
+        first_variance += first_delta * first_delta;
+        second_variance += second_delta * second_delta;
+        covariance += first_delta * second_delta;
+    }
+    let (first_variance, second_variance, covariance) = (
+        first_variance / count,
+        second_variance / count,
+        covariance / count,
+    );
+
+    ((2.0 * first_mean * second_mean + C1) * (2.0 * covariance + C2))
+        / ((first_mean * first_mean + second_mean * second_mean + C1)
+            * (first_variance + second_variance + C2))
+}
+
+fn get_ink_color(pixels: &[Rgb<u8>], coverage: &[f32]) -> Rgb<u8> {
+    let total_coverage: f32 = coverage.iter().sum();
+    if total_coverage == 0.0 {
+        return get_average_pixel(pixels);
+    }
+
+    let mut sum = [0.0f32; 3];
+    for (pixel, coverage) in pixels.iter().zip(coverage) {
+        for (channel_sum, channel) in sum.iter_mut().zip(pixel.0) {
+            *channel_sum += channel as f32 * coverage;
+        }
+    }
+
+    Rgb(sum.map(|channel_sum| (channel_sum / total_coverage).round() as u8))
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+
+    use super::{symbolize, GlyphMatching, GlyphOptions};
+    use crate::{Font, SymbolizeError};
+
+    fn get_options(matching: GlyphMatching) -> GlyphOptions {
+        GlyphOptions {
+            cell_width: 4,
+            cell_height: 4,
+            matching,
+            ..GlyphOptions::new(Font::open("./test-data/glyphs.bdf").unwrap())
+        }
+    }
+
+    fn get_symbols(image: &RgbImage, matching: GlyphMatching) -> String {
+        symbolize(image, &[' ', '|', '-', '/', '#'], &get_options(matching))
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .map(|cell| cell.symbol)
+            .collect()
+    }
+
+    fn get_shapes_image() -> RgbImage {
+        // a vertical line, a diagonal line and a blank block
+        RgbImage::from_fn(12, 4, |x, y| {
+            let is_ink = match x / 4 {
+                0 => x % 4 == 1,
+                1 => x % 4 == 3 - y,
+                _ => false,
+            };
+
+            if is_ink {
+                Rgb([255, 255, 255])
+            } else {
+                Rgb([0, 0, 0])
+            }
+        })
+    }
+
+    #[test]
+    fn matches_glyph_shapes_by_least_squares() {
+        assert_eq!(
+            get_symbols(&get_shapes_image(), GlyphMatching::LeastSquares),
+            "|/ "
+        );
+    }
+
+    #[test]
+    fn matches_glyph_shapes_by_ssim() {
+        assert_eq!(get_symbols(&get_shapes_image(), GlyphMatching::Ssim), "|/ ");
+    }
+
+    #[test]
+    fn colors_glyph_by_its_ink() {
+        let image = RgbImage::from_fn(4, 4, |x, _| {
+            if x == 1 {
+                Rgb([200, 100, 50])
+            } else {
+                Rgb([0, 0, 0])
+            }
+        });
+        let result = symbolize(
+            &image,
+            &['|', '#'],
+            &get_options(GlyphMatching::LeastSquares),
+        );
+
+        assert_eq!(result.unwrap()[0][0].fg, Some(Rgb([200, 100, 50])));
+    }
+
+    #[test]
+    fn returns_error_if_font_has_no_palette_symbols() {
+        let result = symbolize(
+            &get_shapes_image(),
+            &['?'],
+            &get_options(GlyphMatching::LeastSquares),
+        );
+
+        assert!(matches!(result, Err(SymbolizeError::MissingGlyphs)));
+    }
+
+    #[test]
+    fn returns_error_if_cell_size_is_zero() {
+        let options = GlyphOptions {
+            cell_width: 0,
+            ..get_options(GlyphMatching::LeastSquares)
+        };
+        let result = symbolize(&get_shapes_image(), &['|'], &options);
+
+        assert!(matches!(
+            result,
+            Err(SymbolizeError::InvalidOption { name: "cell size", value }) if value == "0x4"
+        ));
+    }
+}
@@ -0,0 +1,168 @@
+use image::Rgb;
+
+use crate::{Cell, ColorDepth, SymbolizeResult};
+
+/// Options of the HTML output, see [`SymbolizeResult::to_html`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlOptions {
+    /// Wraps the `<pre>` element into a complete HTML document
+    pub full_page: bool,
+    /// CSS font family of the symbols, ideally a monospace one
+    pub font_family: String,
+    /// Font size of the symbols in pixels
+    pub font_size: f32,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            full_page: false,
+            font_family: "monospace".to_string(),
+            font_size: 12.0,
+        }
+    }
+}
+
+/// Renders the grid as a `<pre>` element with a `<span>` for every run of identically colored cells.
+/// HTML isn't limited in colors, so any color depth except [`ColorDepth::None`] keeps the exact cell colors.
+pub(crate) fn render(result: &SymbolizeResult, options: &HtmlOptions) -> String {
+    let mut pre = format!(
+        "<pre style=\"font-family: {}; font-size: {}px; line-height: 1;\">",
+        escape(&options.font_family),
+        options.font_size
+    );
+
+    let rows: Vec<String> = result
+        .cells
+        .iter()
+        .map(|row| render_row(row, result.color_depth != ColorDepth::None))
+        .collect();
+    pre.push_str(&rows.join("\n"));
+    pre.push_str("</pre>");
+
+    if !options.full_page {
+        return pre;
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n</head>\n<body>\n{}\n</body>\n</html>\n",
+        pre
+    )
+}
+
+fn render_row(row: &[Cell], colorize: bool) -> String {
+    let colors = |cell: &Cell| {
+        if colorize {
+            (cell.fg, cell.bg)
+        } else {
+            (None, None)
+        }
+    };
+
+    let mut result = String::new();
+    let mut start = 0;
+    while start < row.len() {
+        let (fg, bg) = colors(&row[start]);
+        let end = row[start..]
+            .iter()
+            .position(|cell| colors(cell) != (fg, bg))
+            .map_or(row.len(), |offset| start + offset);
+        let text: String = row[start..end]
+            .iter()
+            .map(|cell| escape(&cell.symbol.to_string()))
+            .collect();
+
+        let style: Vec<String> = [("color", fg), ("background-color", bg)]
+            .into_iter()
+            .filter_map(|(property, color)| {
+                color.map(|color| format!("{}: {}", property, get_hex(&color)))
+            })
+            .collect();
+        if style.is_empty() {
+            result.push_str(&text);
+        } else {
+            result.push_str(&format!(
+                "<span style=\"{}\">{}</span>",
+                style.join("; "),
+                text
+            ));
+        }
+
+        start = end;
+    }
+
+    result
+}
+
+pub(crate) fn get_hex(pixel: &Rgb<u8>) -> String {
+    format!("#{:02x}{:02x}{:02x}", pixel.0[0], pixel.0[1], pixel.0[2])
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.chars()
+        .map(|symbol| match symbol {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '"' => "&quot;".to_string(),
+            '\'' => "&#39;".to_string(),
+            _ => symbol.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgb;
+
+    use super::{render, HtmlOptions};
+    use crate::{Cell, ColorDepth, SymbolizeResult};
+
+    fn get_result(color_depth: ColorDepth) -> SymbolizeResult {
+        let (red, blue) = (Rgb([255, 0, 0]), Rgb([0, 0, 255]));
+        SymbolizeResult {
+            cells: vec![
+                vec![
+                    Cell::new('<', red, None),
+                    Cell::new('&', red, None),
+                    Cell::new('"', blue, Some(red)),
+                ],
+                vec![Cell {
+                    symbol: ' ',
+                    fg: None,
+                    bg: None,
+                }],
+            ],
+            color_depth,
+            legend: vec![],
+        }
+    }
+
+    #[test]
+    fn merges_runs_of_same_color() {
+        assert_eq!(
+            render(&get_result(ColorDepth::TrueColor), &HtmlOptions::default()),
+            "<pre style=\"font-family: monospace; font-size: 12px; line-height: 1;\">\
+             <span style=\"color: #ff0000\">&lt;&amp;</span>\
+             <span style=\"color: #0000ff; background-color: #ff0000\">&quot;</span>\n \
+             </pre>"
+        );
+    }
+
+    #[test]
+    fn renders_uncolored_page() {
+        let html = render(
+            &get_result(ColorDepth::None),
+            &HtmlOptions {
+                full_page: true,
+                font_family: "'Fira Code', monospace".to_string(),
+                font_size: 9.5,
+            },
+        );
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("font-family: &#39;Fira Code&#39;, monospace; font-size: 9.5px;"));
+        assert!(html.contains(">&lt;&amp;&quot;\n </pre>"));
+        assert!(!html.contains("<span"));
+    }
+}
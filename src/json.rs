@@ -0,0 +1,119 @@
+use image::Rgb;
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+use crate::{html::get_hex, Cell, ColorDepth, SymbolizeResult};
+
+/// Serializes the grid as `{ columns, rows, legend: [{ symbol, color }], cells: [[{ symbol, fg, bg }]] }`.
+/// Like HTML, JSON keeps the exact cell colors unless the color depth is [`ColorDepth::None`],
+/// then `fg` and `bg` are `null`.
+impl Serialize for SymbolizeResult {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let colorize = self.color_depth != ColorDepth::None;
+        let legend: Vec<LegendEntry> = self
+            .legend
+            .iter()
+            .map(|(symbol, color)| LegendEntry {
+                symbol: *symbol,
+                color: get_hex(color),
+            })
+            .collect();
+        let cells: Vec<Vec<CellEntry>> = self
+            .cells
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| CellEntry::new(cell, colorize))
+                    .collect()
+            })
+            .collect();
+
+        let mut result = serializer.serialize_struct("SymbolizeResult", 4)?;
+        result.serialize_field(
+            "columns",
+            &self.cells.iter().map(Vec::len).max().unwrap_or(0),
+        )?;
+        result.serialize_field("rows", &self.cells.len())?;
+        result.serialize_field("legend", &legend)?;
+        result.serialize_field("cells", &cells)?;
+        result.end()
+    }
+}
+
+#[derive(Serialize)]
+struct LegendEntry {
+    symbol: char,
+    color: String,
+}
+
+#[derive(Serialize)]
+struct CellEntry {
+    symbol: char,
+    fg: Option<String>,
+    bg: Option<String>,
+}
+
+impl CellEntry {
+    fn new(cell: &Cell, colorize: bool) -> Self {
+        let color =
+            |color: Option<Rgb<u8>>| color.filter(|_| colorize).map(|color| get_hex(&color));
+
+        Self {
+            symbol: cell.symbol,
+            fg: color(cell.fg),
+            bg: color(cell.bg),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{open, Rgb};
+
+    use crate::{Cell, ColorDepth, SymbolizeResult, Symbolizer};
+
+    #[test]
+    fn serializes_cells_with_colors() {
+        let (red, blue) = (Rgb([255, 0, 0]), Rgb([0, 0, 255]));
+        let mut result = SymbolizeResult {
+            cells: vec![vec![
+                Cell::new('"', red, Some(blue)),
+                Cell::new('#', blue, None),
+            ]],
+            color_depth: ColorDepth::TrueColor,
+            legend: vec![('"', red), ('#', blue)],
+        };
+
+        assert_eq!(
+            result.to_json(),
+            "{\"columns\":2,\"rows\":1,\
+             \"legend\":[{\"symbol\":\"\\\"\",\"color\":\"#ff0000\"},{\"symbol\":\"#\",\"color\":\"#0000ff\"}],\
+             \"cells\":[[{\"symbol\":\"\\\"\",\"fg\":\"#ff0000\",\"bg\":\"#0000ff\"},\
+             {\"symbol\":\"#\",\"fg\":\"#0000ff\",\"bg\":null}]]}"
+        );
+
+        result.color_depth = ColorDepth::None;
+        assert!(result
+            .to_json()
+            .ends_with("[{\"symbol\":\"\\\"\",\"fg\":null,\"bg\":null},{\"symbol\":\"#\",\"fg\":null,\"bg\":null}]]}"));
+    }
+
+    #[test]
+    fn fills_legend_from_palette() {
+        let palette = [' ', '.', '@'];
+        let result = Symbolizer::new()
+            .scale(0.03)
+            .palette(&palette)
+            .symbolize(open("./test-data/ferris.png").unwrap())
+            .unwrap();
+
+        assert!(!result.legend.is_empty());
+        assert!(result
+            .legend
+            .iter()
+            .all(|(symbol, _)| palette.contains(symbol)));
+        assert!(result.cells.iter().flatten().all(|cell| result
+            .legend
+            .iter()
+            .any(|(symbol, color)| cell.symbol == *symbol && cell.fg == Some(*color))));
+    }
+}
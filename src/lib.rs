@@ -1,31 +1,43 @@
-//! This crate provides [`symbolize`] function that allows you to convert bitmap images into fine text art.
-//! It supports scaling the [`symbolize`]d images as well as coloring them for terminals with RGB-support.
+//! This crate provides [`Symbolizer`] that allows you to convert bitmap images into fine text art.
+//! It supports scaling the symbolized images as well as coloring them for terminals.
 //!
-//! [`SymbolizeResult`] is a wrapper that allows you to easy convert a result to [`Vec<String>`], [`Vec<u8>`] or [`String`]
+//! Every option of [`Symbolizer`] has a default, see [`SymbolizeOptions`]: pick a rendering [`Mode`],
+//! e.g. [`Mode::Braille`] that packs 2x4 pixels into a single braille glyph for a much higher resolution,
+//! fit the result into a budget of columns and rows, reduce the colors for terminals without RGB-support
+//! with [`ColorDepth`] or put them to the [`Background`] of the cells.
 //!
-//! The "original_image" parameter provides an original image as a [`DynamicImage`]
+//! [`SymbolizeResult`] is a grid of [`Cell`]s with their symbols and colors,
+//! it allows you to easy convert a result to [`Vec<String>`], [`Vec<u8>`] or [`String`]
+//! for terminals, or render it as HTML with [`SymbolizeResult::to_html`], as SVG with [`SymbolizeResult::to_svg`]
+//! or draw it into an image with [`SymbolizeResult::to_image`].
+//! With the `serde` feature (enabled by default) it serializes with its palette legend,
+//! e.g. to JSON with `SymbolizeResult::to_json`
 //!
-//! The "palette" parameter determines which characters will be used when converting the image.
-//! The symbols are arranged in descending order of the frequency of their appearance on the image.
+//! Animated GIF, APNG and WebP images are decoded with [`open_frames`] and symbolized frame by frame
+//! with [`Symbolizer::symbolize_frames`], the symbolized frames can be recorded for asciinema
+//! with [`to_asciicast`]
 //!
-//! The "scale" parameter determines the size of the output image relative to the size of the original.
-//!
-//! The "filter_type" parameter defines what type of filtering will be used when scaling the image. For more info read [`FilterType`] docs.
-//!
-//! The "colorize" parameter determines whether the output should be colorized for RGB-terminals or not.
+//! [`symbolize`] is kept for compatibility, it takes the image, the scale, the palette,
+//! the [`FilterType`] and whether the result should be colorized for RGB-terminals.
 //!
 //! # Example usage:
 //!
 //! ```ignore
 //! use image::{imageops::FilterType, open};
 //! use std::{process, error::Error};
-//! use symbolize::symbolize;
+//! use symbolize::{symbolize, Symbolizer};
 //!
 //! fn main() -> Result<(), Box<dyn Error>> {
-//!     let result = symbolize(
-//!         open("./path/to/image.png")?
-//!         &vec!['*', '#', '@', ' '],
+//!     let result = Symbolizer::new()
+//!         .scale(0.1)
+//!         .palette(&['*', '#', '@', ' '])
+//!         .symbolize(open("./path/to/image.png")?);
+//!
+//!     // the same with the compatibility wrapper
+//!     let _ = symbolize(
+//!         open("./path/to/image.png")?,
 //!         0.1,
+//!         &['*', '#', '@', ' '],
 //!         FilterType::Nearest,
 //!         false,
 //!     );
@@ -68,108 +80,428 @@
 //!                                                           @@
 //! ```
 
-use std::{
-    collections::{hash_map::Entry, HashMap},
-    error::Error,
-    io,
-};
+use std::path::Path;
 
-use crossterm::style::{style, Color, Stylize};
-use image::{
-    imageops::{resize, FilterType},
-    DynamicImage, Rgb, RgbImage,
-};
+use image::{imageops::FilterType, DynamicImage, GrayImage, Rgb, RgbImage, RgbaImage};
 
-/// Helper wrapper struct that provides some [`Into`] implementations for easier convertation
-pub struct SymbolizeResult(pub Vec<Vec<String>>);
+mod alpha;
+mod animation;
+mod ansi;
+mod asciicast;
+mod blocks;
+mod braille;
+mod color;
+mod dither;
+mod error;
+mod font;
+mod glyph;
+mod html;
+#[cfg(feature = "serde")]
+mod json;
+mod quantize;
+mod ramp;
+mod raster;
+mod svg;
+mod symbolizer;
 
-impl Into<String> for SymbolizeResult {
-    fn into(self) -> String {
-        self.0
+pub use alpha::AlphaOptions;
+pub use animation::{load_frames, open_frames, SymbolizedFrame, SymbolizedFrames};
+pub use ansi::{Background, ColorDepth};
+pub use asciicast::{to_asciicast, AsciicastOptions};
+use blocks::BlockLayout;
+pub use braille::BrailleOptions;
+pub use color::DistanceMetric;
+pub use dither::Dither;
+pub use error::SymbolizeError;
+pub use font::Font;
+pub use glyph::{GlyphMatching, GlyphOptions};
+pub use html::HtmlOptions;
+pub use quantize::Quantizer;
+pub use ramp::{RampCurve, RampOptions};
+pub use raster::RasterOptions;
+pub use svg::SvgOptions;
+pub use symbolizer::{SymbolizeOptions, Symbolizer, DEFAULT_PALETTE};
+
+/// Grid of the symbolized cells with the color depth they are rendered with.
+/// Provides some [`From`] implementations rendering it for terminals.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolizeResult {
+    /// Rows of the cells
+    pub cells: Vec<Vec<Cell>>,
+    /// Colors the cells are rendered with, [`ColorDepth::None`] renders only the symbols
+    pub color_depth: ColorDepth,
+    /// Palette symbols with the colors they stand for, filled only by [`Mode::Palette`]
+    pub legend: Vec<(char, Rgb<u8>)>,
+}
+
+impl SymbolizeResult {
+    /// Renders every row for a terminal, the colors are set with ANSI escape sequences
+    pub fn to_ansi_rows(&self) -> Vec<String> {
+        self.cells
             .iter()
-            .map(|row| row.join(""))
-            .collect::<Vec<String>>()
-            .join("\n")
+            .map(|row| ansi::render_row(row, self.color_depth))
+            .collect()
+    }
+
+    /// Renders the grid as a `<pre>` element (or a complete page) with colored `<span>`s
+    pub fn to_html(&self, options: &HtmlOptions) -> String {
+        html::render(self, options)
+    }
+
+    /// Renders the grid as an SVG image
+    pub fn to_svg(&self, options: &SvgOptions) -> String {
+        svg::render(self, options)
+    }
+
+    /// Serializes the dimensions, the legend and the cells as JSON, colors are `#rrggbb` strings
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        // the result has only strings, numbers and sequences, so it always serializes
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Draws the grid into an image with a bitmap (or a user-supplied) font.
+    /// Fails with [`SymbolizeError::OversizedOutput`] if the image would have too many pixels
+    pub fn to_image(&self, options: &RasterOptions) -> Result<RgbaImage, SymbolizeError> {
+        raster::render(self, options)
+    }
+
+    /// Draws the grid into an image and saves it in the format chosen by the extension of the path,
+    /// any format supported by the `image` crate works
+    pub fn save_image(
+        &self,
+        path: impl AsRef<Path>,
+        options: &RasterOptions,
+    ) -> Result<(), SymbolizeError> {
+        raster::save(self.to_image(options)?, path.as_ref())
     }
 }
 
-impl Into<Vec<u8>> for SymbolizeResult {
-    fn into(self) -> Vec<u8> {
-        self.0
-            .iter()
-            .map(|row| row.join(""))
-            .collect::<Vec<String>>()
-            .join("\n")
-            .into_bytes()
+impl From<SymbolizeResult> for String {
+    fn from(result: SymbolizeResult) -> Self {
+        result.to_ansi_rows().join("\n")
     }
 }
 
-impl Into<Vec<String>> for SymbolizeResult {
-    fn into(self) -> Vec<String> {
-        self.0
-            .iter()
-            .map(|row| row.join(""))
-            .collect::<Vec<String>>()
+impl From<SymbolizeResult> for Vec<u8> {
+    fn from(result: SymbolizeResult) -> Self {
+        String::from(result).into_bytes()
     }
 }
 
+impl From<SymbolizeResult> for Vec<String> {
+    fn from(result: SymbolizeResult) -> Self {
+        result.to_ansi_rows()
+    }
+}
+
+/// A single symbol of the result with the colors it should be painted with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell {
+    /// Symbol of the cell
+    pub symbol: char,
+    /// Color of the symbol, [`None`] keeps the default color
+    pub fg: Option<Rgb<u8>>,
+    /// Color of the cell background, [`None`] keeps the default background
+    pub bg: Option<Rgb<u8>>,
+}
+
+impl Cell {
+    pub(crate) fn new(symbol: char, fg: Rgb<u8>, bg: Option<Rgb<u8>>) -> Self {
+        Self {
+            symbol,
+            fg: Some(fg),
+            bg,
+        }
+    }
+}
+
+/// Defines how the pixels of the image are turned into symbols
+#[derive(Debug, Clone)]
+pub enum Mode {
+    /// Every pixel becomes a palette symbol assigned to the representative colors of the image,
+    /// by default to the most used ones
+    Palette(PaletteOptions),
+    /// Every pixel becomes a palette symbol picked by its perceived lightness,
+    /// the palette is ordered from the darkest symbol to the lightest one
+    Ramp(RampOptions),
+    /// Every block of pixels becomes the palette symbol whose shape, rasterized with the given font,
+    /// resembles the block the most
+    Glyph(GlyphOptions),
+    /// Every 2x4 block of pixels is packed into a single Unicode braille glyph (U+2800–U+28FF).
+    /// The palette is not used in this mode.
+    Braille(BrailleOptions),
+    /// Every two vertically stacked pixels are drawn as a single `▀` glyph with the top pixel
+    /// as a foreground and the bottom one as a background color. The palette is not used in this mode.
+    /// Without colorizing the bright pixels are filled with `▀`, `▄` or `█`.
+    HalfBlock,
+    /// Every 2x2 block of pixels is drawn as a quadrant block element (`▖`, `▞`, `▙`…)
+    /// with two colors chosen to match the block best. The palette is not used in this mode.
+    Quadrant,
+    /// Same as [`Mode::Quadrant`] but for 2x3 blocks drawn as sextants (U+1FB00–U+1FB3B).
+    /// Requires a font supporting Symbols for Legacy Computing.
+    Sextant,
+    /// Same as [`Mode::Quadrant`] but for 2x4 blocks drawn as octants (U+1CD00–U+1CDE5).
+    /// Requires a font supporting Unicode 16 Symbols for Legacy Computing Supplement.
+    Octant,
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Mode::Palette(PaletteOptions::default())
+    }
+}
+
+/// Options of the [`Mode::Palette`] rendering mode
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PaletteOptions {
+    /// Defines how the palette colors are chosen from the image
+    pub quantizer: Quantizer,
+    /// Defines how the error of matching a pixel with a palette color is spread over the image.
+    /// The error is measured in the color space of [`SymbolizeOptions::metric`]: RGB or CIELAB.
+    pub dither: Dither,
+}
+
+/// Height of a terminal character cell relative to its width, typical for monospace fonts
+pub const DEFAULT_CELL_ASPECT_RATIO: f32 = 2.0;
+
 /// Main function of this crate. Turns your bitmap image into text art.
+///
+/// Kept for compatibility, [`Symbolizer`] gives access to all of the options.
+/// Like the earlier versions it discards the alpha channel, [`Symbolizer::alpha`] leaves the transparent cells blank.
 pub fn symbolize(
     original_image: DynamicImage,
     scale: f32,
     palette: &[char],
     filter_type: FilterType,
     colorize: bool,
-) -> Result<SymbolizeResult, Box<dyn Error>> {
-    if palette.is_empty() {
-        return Err(Box::new(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            "pallete should contain at leasst one symbol, aborting",
-        )));
-    }
-
-    if scale < 0.0 {
-        return Err(Box::new(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            "scale should be > 0, aborting",
-        )));
-    }
-
-    let original_image_rgb = original_image.into_rgb8();
-    let scaled_image = resize(
-        &original_image_rgb,
-        (original_image_rgb.width() as f32 * scale) as u32,
-        (original_image_rgb.height() as f32 * scale) as u32,
+) -> Result<SymbolizeResult, SymbolizeError> {
+    Symbolizer::new()
+        .scale(scale)
+        .palette(palette)
+        .filter_type(filter_type)
+        .colorize(colorize)
+        .symbolize(original_image)
+}
+
+/// Computes the largest `scale` that keeps the result of an image with the given dimensions
+/// within the budget of `columns` and `rows`.
+/// The aspect ratio of the image is preserved for cells of the given aspect ratio,
+/// an absent budget doesn't limit its dimension. Without any budget the original size (`1.0`) is returned.
+pub fn get_scale_to_fit(
+    (image_width, image_height): (u32, u32),
+    columns: Option<u32>,
+    rows: Option<u32>,
+    cell_aspect_ratio: f32,
+) -> f32 {
+    // the half of a cell is added so the float error can't truncate the result below the budget
+    let fit = |budget: u32, size: f32| (budget as f32 + 0.5) / size.max(1.0);
+    let scales = [
+        columns.map(|columns| fit(columns, image_width as f32 * cell_aspect_ratio)),
+        rows.map(|rows| fit(rows, image_height as f32)),
+    ];
+
+    scales.into_iter().flatten().reduce(f32::min).unwrap_or(1.0)
+}
+
+/// Validates the options and computes the amount of columns and rows of the result
+/// for an image with the given dimensions.
+///
+/// The scale defines the amount of rows, the amount of columns is stretched by the cell aspect ratio
+/// so the result keeps the proportions of the image.
+/// Every mode produces the same amount of rows and columns for the same scale,
+/// sub-pixel modes just sample more pixels of the original image per symbol.
+fn get_grid_size(
+    (width, height): (u32, u32),
+    options: &SymbolizeOptions,
+) -> Result<(u32, u32), SymbolizeError> {
+    let cell_aspect_ratio = options.cell_aspect_ratio;
+
+    if matches!(
+        options.mode,
+        Mode::Palette(_) | Mode::Ramp(_) | Mode::Glyph(_)
+    ) && options.palette.is_empty()
+    {
+        return Err(SymbolizeError::EmptyPalette);
+    }
+
+    if options.scale <= 0.0 || !options.scale.is_finite() {
+        return Err(SymbolizeError::InvalidScale(options.scale));
+    }
+
+    if cell_aspect_ratio <= 0.0 || !cell_aspect_ratio.is_finite() {
+        return Err(SymbolizeError::InvalidCellAspectRatio(cell_aspect_ratio));
+    }
+
+    let scale = if options.columns.is_some() || options.rows.is_some() {
+        get_scale_to_fit(
+            (width, height),
+            options.columns,
+            options.rows,
+            cell_aspect_ratio,
+        )
+    } else {
+        options.scale
+    };
+    let (columns, rows) = (
+        (width as f32 * scale * cell_aspect_ratio) as u32,
+        (height as f32 * scale) as u32,
+    );
+    if columns == 0 || rows == 0 {
+        return Err(SymbolizeError::ZeroSizedOutput);
+    }
+
+    Ok((columns, rows))
+}
+
+/// Symbolizes the image, the palette mode matches the pixels with the given legend
+/// instead of picking the palette colors from the image
+fn symbolize_with_options(
+    original_image: DynamicImage,
+    options: &SymbolizeOptions,
+    legend: Option<&[PixelWithSymbol]>,
+) -> Result<SymbolizeResult, SymbolizeError> {
+    let (palette, filter_type) = (&options.palette[..], options.filter_type);
+    let (columns, rows) =
+        get_grid_size((original_image.width(), original_image.height()), options)?;
+    let colorize = options.color_depth != ColorDepth::None;
+    let layout = match options.mode {
+        Mode::Quadrant => BlockLayout::Quadrant,
+        Mode::Sextant => BlockLayout::Sextant,
+        _ => BlockLayout::Octant,
+    };
+    let (width, height) = match &options.mode {
+        Mode::Palette(_) | Mode::Ramp(_) => (columns, rows),
+        Mode::Glyph(glyph_options) => columns
+            .checked_mul(glyph_options.cell_width)
+            .zip(rows.checked_mul(glyph_options.cell_height))
+            .ok_or_else(|| SymbolizeError::InvalidOption {
+                name: "cell size",
+                value: format!("{}x{}", glyph_options.cell_width, glyph_options.cell_height),
+            })?,
+        Mode::Braille(_) => (columns * braille::CELL_WIDTH, rows * braille::CELL_HEIGHT),
+        // a half-block cell is twice as detailed vertically as a palette mode cell
+        Mode::HalfBlock => (columns, rows * blocks::HALF_BLOCK_HEIGHT),
+        Mode::Quadrant | Mode::Sextant | Mode::Octant => {
+            (columns * blocks::BLOCK_WIDTH, rows * layout.height())
+        }
+    };
+    let (scaled_image, opacity) = alpha::resize(
+        original_image,
+        width,
+        height,
         filter_type,
+        options.alpha.as_ref(),
     );
-    let colors_to_use = get_most_used_colours_with_symbols(&scaled_image, palette);
-
-    let mut result = vec![];
-    for row in scaled_image.rows() {
-        let mut result_row = vec![];
-        for pixel in row {
-            let (symbol, average_pixel) = get_symbol_by_pixel(&colors_to_use, pixel)?;
-
-            let str_symbol = if colorize {
-                format!(
-                    "{}",
-                    style(symbol.to_string()).with(Color::from((
-                        average_pixel.0[0],
-                        average_pixel.0[1],
-                        average_pixel.0[2]
-                    )))
-                )
-            } else {
-                symbol.to_string()
-            };
-            result_row.push(str_symbol.clone());
-            result_row.push(str_symbol);
+
+    let mut result_legend = vec![];
+    let cells = match &options.mode {
+        Mode::Palette(palette_options) => {
+            let (cells, palette_legend) = symbolize_by_palette(
+                &scaled_image,
+                opacity
+                    .as_ref()
+                    .zip(options.alpha.map(|alpha| alpha.threshold)),
+                palette,
+                legend,
+                options.metric,
+                *palette_options,
+            )?;
+            result_legend = palette_legend;
+            cells
+        }
+        Mode::Ramp(ramp_options) => ramp::symbolize(&scaled_image, palette, ramp_options),
+        Mode::Glyph(glyph_options) => glyph::symbolize(&scaled_image, palette, glyph_options)?,
+        Mode::Braille(braille_options) => braille::symbolize(&scaled_image, *braille_options),
+        Mode::HalfBlock => blocks::symbolize_half_blocks(&scaled_image, colorize),
+        Mode::Quadrant | Mode::Sextant | Mode::Octant => {
+            blocks::symbolize_blocks(&scaled_image, layout, colorize)
         }
+    };
+    let cells = match (opacity, options.alpha) {
+        (Some(opacity), Some(alpha_options)) => {
+            alpha::clear_transparent(cells, &opacity, alpha_options.threshold)
+        }
+        _ => cells,
+    };
+
+    Ok(get_result(
+        cells,
+        result_legend,
+        options.color_depth,
+        options.background,
+    ))
+}
 
-        result.push(result_row)
+/// Palette symbols with the colors they stand for
+type Legend = Vec<(char, Rgb<u8>)>;
+
+fn symbolize_by_palette(
+    scaled_image: &RgbImage,
+    opacity: Option<(&GrayImage, u8)>,
+    palette: &[char],
+    legend: Option<&[PixelWithSymbol]>,
+    metric: DistanceMetric,
+    options: PaletteOptions,
+) -> Result<(Vec<Vec<Cell>>, Legend), SymbolizeError> {
+    let picked_colors;
+    let colors_to_use = match legend {
+        Some(legend) => legend,
+        None => {
+            picked_colors =
+                get_colours_with_symbols(scaled_image, opacity, palette, options.quantizer);
+            &picked_colors[..]
+        }
+    };
+    // no pixel is opaque enough to pick a color from, so every cell is left blank
+    if colors_to_use.is_empty() && opacity.is_some() {
+        let blank = Cell {
+            symbol: ' ',
+            fg: None,
+            bg: None,
+        };
+        let cells =
+            vec![vec![blank; scaled_image.width() as usize]; scaled_image.height() as usize];
+        return Ok((cells, vec![]));
     }
+    let matches = get_symbols_by_pixels(scaled_image, colors_to_use, metric, options.dither)?;
+
+    let cells = matches
+        .chunks(scaled_image.width().max(1) as usize)
+        .map(|row| {
+            row.iter()
+                .map(|PixelWithSymbol { pixel, symbol }| Cell::new(*symbol, *pixel, None))
+                .collect()
+        })
+        .collect();
+    let legend = colors_to_use
+        .iter()
+        .map(|PixelWithSymbol { pixel, symbol }| (*symbol, *pixel))
+        .collect();
+
+    Ok((cells, legend))
+}
+
+fn get_result(
+    cells: Vec<Vec<Cell>>,
+    legend: Legend,
+    color_depth: ColorDepth,
+    background: Background,
+) -> SymbolizeResult {
+    let cells = if color_depth == ColorDepth::None {
+        cells
+    } else {
+        cells
+            .into_iter()
+            .map(|row| row.iter().map(|cell| background.apply(cell)).collect())
+            .collect()
+    };
 
-    Ok(SymbolizeResult(result))
+    SymbolizeResult {
+        cells,
+        color_depth,
+        legend,
+    }
 }
 
 #[derive(Debug)]
@@ -184,111 +516,189 @@ impl PixelWithSymbol {
     }
 }
 
-fn get_most_used_colours_with_symbols(image: &RgbImage, symbols: &[char]) -> Vec<PixelWithSymbol> {
-    let mut colors_uses: HashMap<&image::Rgb<u8>, usize> = HashMap::new();
-    for pixel in image.pixels() {
-        match colors_uses.entry(pixel) {
-            Entry::Vacant(entry) => {
-                entry.insert(1);
-            }
-            Entry::Occupied(mut entry) => {
-                *entry.get_mut() += 1;
+/// Picks the palette colors from the image, the pixels less opaque than the threshold are left out
+fn get_colours_with_symbols(
+    image: &RgbImage,
+    opacity: Option<(&GrayImage, u8)>,
+    symbols: &[char],
+    quantizer: Quantizer,
+) -> Vec<PixelWithSymbol> {
+    let colors = match opacity {
+        Some((opacity, threshold)) => quantize::quantize(
+            image
+                .pixels()
+                .zip(opacity.pixels())
+                .filter(|(_, alpha)| alpha.0[0] >= threshold)
+                .map(|(pixel, _)| pixel),
+            symbols.len(),
+            quantizer,
+        ),
+        None => quantize::quantize(image.pixels(), symbols.len(), quantizer),
+    };
+
+    // when the image has fewer colors than the palette, the last symbols of the palette are used
+    colors
+        .iter()
+        .zip(&symbols[symbols.len() - colors.len()..])
+        .map(|((pixel, _), symbol)| PixelWithSymbol::new(*pixel, *symbol))
+        .collect()
+}
+
+/// Matches every pixel of the image (row by row) with the closest palette color
+fn get_symbols_by_pixels<'a>(
+    image: &RgbImage,
+    pixels_with_symbols: &'a [PixelWithSymbol],
+    metric: DistanceMetric,
+    dither: Dither,
+) -> Result<Vec<&'a PixelWithSymbol>, SymbolizeError> {
+    // the image isn't empty here, so no colors are picked only for an empty palette
+    if pixels_with_symbols.is_empty() {
+        return Err(SymbolizeError::EmptyPalette);
+    }
+
+    let anchors: Vec<[f32; 3]> = pixels_with_symbols
+        .iter()
+        .map(|PixelWithSymbol { pixel, .. }| metric.to_space(pixel))
+        .collect();
+
+    // ordered dithering thresholds span the smallest gap between the palette colors: the offset
+    // is added to every channel, so it's divided by √3 to be as long as the gap at most.
+    // Thresholds stay below half of it, so the pixels that already match a palette color aren't moved
+    let mut min_gap = 0.0f32;
+    for (idx, first) in anchors.iter().enumerate() {
+        for second in &anchors[idx + 1..] {
+            let gap = DistanceMetric::Euclidean.distance_in_space(first, second);
+            if gap > 0.0 && (min_gap == 0.0 || gap < min_gap) {
+                min_gap = gap;
             }
         }
     }
-    let mut colours_uses_vec: Vec<(&Rgb<u8>, usize)> = colors_uses.into_iter().collect();
-    colours_uses_vec.sort_by_key(|(_, count)| *count);
+    let spread = [min_gap / 3f32.sqrt(); 3];
 
-    let (start, end) = (
-        colours_uses_vec
-            .len()
-            .checked_sub(symbols.len())
-            .unwrap_or(0),
-        colours_uses_vec.len(),
-    );
+    let values = image.pixels().map(|pixel| metric.to_space(pixel)).collect();
+    let indices = dither::dither(values, image.width() as usize, dither, spread, |value| {
+        let mut nearest = 0;
+        let mut comparison = f32::MAX;
+        for (idx, anchor) in anchors.iter().enumerate() {
+            let pretendent_comparison = metric.distance_in_space(value, anchor);
+            if pretendent_comparison < comparison {
+                nearest = idx;
+                comparison = pretendent_comparison;
+            }
+        }
 
-    let mut symbol_idx = symbols.len() - 1;
-    colours_uses_vec
-        .drain(start..end)
-        .map(|(pixel, _)| {
-            let pixel_with_symbol = PixelWithSymbol::new(*pixel, symbols[symbol_idx]);
-            symbol_idx = symbol_idx.checked_sub(1).unwrap_or(0);
+        (nearest, anchors[nearest])
+    });
 
-            pixel_with_symbol
-        })
-        .collect()
+    Ok(indices
+        .into_iter()
+        .map(|idx| &pixels_with_symbols[idx])
+        .collect())
 }
 
-fn get_symbol_by_pixel(
-    pixels_with_symbols: &[PixelWithSymbol],
-    pixel_to_compare: &Rgb<u8>,
-) -> Result<(char, Rgb<u8>), io::Error> {
-    let mut char = None;
-    let mut rgb_pixel = None;
-    let mut comparison = None;
-
-    for PixelWithSymbol { pixel, symbol } in pixels_with_symbols {
-        let pretendent_comparison = get_pixel_comparison(pixel_to_compare, pixel);
-        if comparison.is_none() || pretendent_comparison < comparison.unwrap() {
-            char = Some(*symbol);
-            comparison = Some(pretendent_comparison);
-            rgb_pixel = Some(*pixel);
-        }
-    }
+/// Relative luminance of the pixel in 0-255 range (Rec. 709 coefficients)
+fn get_luminance(pixel: &Rgb<u8>) -> f32 {
+    0.2126 * pixel.0[0] as f32 + 0.7152 * pixel.0[1] as f32 + 0.0722 * pixel.0[2] as f32
+}
 
-    if let (Some(char), Some(rgb_pixel)) = (char, rgb_pixel) {
-        return Ok((char, rgb_pixel));
+fn get_average_pixel(pixels: &[Rgb<u8>]) -> Rgb<u8> {
+    if pixels.is_empty() {
+        return Rgb([0, 0, 0]);
     }
 
-    Err(io::Error::new(
-        io::ErrorKind::Other,
-        "unexpected error, can't find matching char for a pixel. aborting",
-    ))
-}
+    let mut sum = [0usize; 3];
+    for pixel in pixels {
+        for (channel_sum, channel) in sum.iter_mut().zip(pixel.0) {
+            *channel_sum += channel as usize;
+        }
+    }
 
-fn get_pixel_comparison(first: &Rgb<u8>, second: &Rgb<u8>) -> usize {
-    ((first.0[0] as i16 - second.0[0] as i16).abs()
-        + (first.0[1] as i16 - second.0[1] as i16).abs()
-        + (first.0[2] as i16 - second.0[2] as i16).abs()) as usize
+    Rgb(sum.map(|channel_sum| (channel_sum / pixels.len()) as u8))
 }
 
 #[cfg(test)]
 mod tests {
-    use image::{imageops::FilterType, open};
+    use image::{imageops::FilterType, open, DynamicImage, Rgb, RgbImage, Rgba, RgbaImage};
 
-    use crate::symbolize;
+    use crate::{
+        get_scale_to_fit, symbolize, AlphaOptions, Background, BrailleOptions, Cell, ColorDepth,
+        DistanceMetric, Dither, Font, GlyphOptions, Mode, PaletteOptions, Quantizer, RampOptions,
+        RasterOptions, SymbolizeError, Symbolizer, DEFAULT_CELL_ASPECT_RATIO,
+    };
+
+    fn symbolize_ferris_with_mode(scale: f32, color_depth: ColorDepth, mode: Mode) -> Vec<String> {
+        Symbolizer::new()
+            .scale(scale)
+            .color_depth(color_depth)
+            .mode(mode)
+            .symbolize(open("./test-data/ferris.png").unwrap())
+            .unwrap()
+            .into()
+    }
 
     fn get_ferris() -> Vec<&'static str> {
         vec![
             "                                                                        ",
             "                                                                        ",
             "                                                                        ",
-            "                              @@  @@@@  @@                              ",
-            "                          @@  @@@@@@@@@@@@@@@@@@                        ",
-            "                    @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@                    ",
-            "                    @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@            @@      ",
-            "  @@  @@          @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@      @@@@      ",
-            "  @@  @@@@        @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@      @@@@  @@@@",
-            "@@@@  @@@@    @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@  @@@@@@@@  ",
-            "  @@@@@@@@    @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@    @@@@@@@@  ",
-            "    @@@@@@  @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@    ",
-            "      @@@@  @@@@@@@@@@@@@@@@@@&&    @@@@&&&&  @@@@@@@@@@@@@@  @@        ",
-            "        @@@@@@@@@@@@@@@@@@@@@@&&    @@@@      @@@@@@@@@@@@@@@@@@        ",
-            "          @@@@@@@@@@@@@@@@@@@@      @@@@      @@@@@@@@@@@@@@@@@@        ",
-            "        @@@@@@$$@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@$$$$@@@@@@      ",
-            "          @@@@$$  $$$$@@@@@@@@@@@@@@    @@@@@@@@@@$$$$    $$@@@@        ",
-            "            @@  $$        $$$$$$$$$$$$$$$$$$$$$$        $$  @@          ",
-            "              @@                                            @@          ",
-            "                                                          @@            ",
-            "                                                                        ",
+            "                              @@@  @@@  @@@                             ",
+            "                         @@@@ @@@@@@@@@@@@@ @@@                         ",
+            "                     @@@ @@@@@@@@@@@@@@@@@@@@@@@ @@@                    ",
+            "                     @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@             @      ",
+            "      @@         @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@      @@@@   @@ ",
+            " @@@  @@@@        @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@      @@@@@  @@@ ",
+            " @@@@ @@@@@   @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@  @@@@@@@@@@ ",
+            " @@@@@@@@@@    @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@    @@@@@@@   ",
+            "   @@@@@@@ @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@ @@@@     ",
+            "      @@@@  @@@@@@@@@@@@@@@@@@&&&  @@@@@ &&  @@@@@@@@@@@@@@@@ @@@       ",
+            "        @@@@@@@@@@@@@@@@@@@@@  &    @@@@ &&   @@@@@@@@@@@@@@@@@         ",
+            "          @@@@@@@@@@@@@@@@@@@@     @@@@@     @@@@@@@@@@@@@@@@@@@        ",
+            "        @@@@@$$$@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@$$$$@@@@@       ",
+            "         @@@@  $$  $$$$@@@@@@@@@@@@@    @@@@@@@@@@$$$$$   $  @@@        ",
+            "           @@@  $$         $$$$$$$$$$$$$$$$$$$$$         $  @@@         ",
+            "             @@@  $                                     $  @@           ",
+            "               @@                                         @@            ",
+            "                 @                                                      ",
             "                                                                        ",
             "                                                                        ",
             "                                                                        ",
         ]
     }
 
+    /// Colorized ferris as it was painted before the colors of neighbouring cells were grouped,
+    /// with the colors set and reset around every single symbol
+    fn get_per_cell_colorized_ferris() -> &'static str {
+        "\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\n\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\n\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\n\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\n\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;255;255;255m&\u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\n\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;165;43;0m$\u{1b}[39m\u{1b}[38;2;165;43;0m$\u{1b}[39m\u{1b}[38;2;165;43;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;165;43;0m$\u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;165;43;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\n\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;247;76;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\n\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m\u{1b}[38;2;0;0;0m \u{1b}[39m"
+    }
+
     fn get_colorized_ferris() -> &'static str {
-        "\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\n\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\n\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\n\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\n\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\n\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;165;43;0m&\u{1b}[39m\u{1b}[38;2;165;43;0m&\u{1b}[39m\u{1b}[38;2;165;43;0m&\u{1b}[39m\u{1b}[38;2;165;43;0m&\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;165;43;0m&\u{1b}[39m\u{1b}[38;2;165;43;0m&\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;247;76;0m$\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\n\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\n\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m\u{1b}[38;2;0;0;0m@\u{1b}[39m"
+        "\u{1b}[38;2;0;0;0m                        \u{1b}[0m\n\u{1b}[38;2;0;0;0m        \u{1b}[38;2;247;76;0m@@@@@@\u{1b}[38;2;0;0;0m \u{1b}[38;2;247;76;0m@\u{1b}[38;2;0;0;0m        \u{1b}[0m\n\u{1b}[38;2;0;0;0m  \u{1b}[38;2;247;76;0m@\u{1b}[38;2;0;0;0m   \u{1b}[38;2;247;76;0m@@@@@@@@@@@@@\u{1b}[38;2;0;0;0m  \u{1b}[38;2;247;76;0m@\u{1b}[38;2;0;0;0m \u{1b}[38;2;247;76;0m@\u{1b}[0m\n\u{1b}[38;2;247;76;0m@@@@\u{1b}[38;2;0;0;0m \u{1b}[38;2;247;76;0m@@@@@@@@@@@@@@\u{1b}[38;2;0;0;0m  \u{1b}[38;2;247;76;0m@@\u{1b}[38;2;0;0;0m \u{1b}[0m\n\u{1b}[38;2;0;0;0m   \u{1b}[38;2;247;76;0m@@@@@@@\u{1b}[38;2;255;255;255m&\u{1b}[38;2;0;0;0m \u{1b}[38;2;247;76;0m@\u{1b}[38;2;0;0;0m  \u{1b}[38;2;247;76;0m@@@@@@\u{1b}[38;2;0;0;0m   \u{1b}[0m\n\u{1b}[38;2;0;0;0m   \u{1b}[38;2;247;76;0m@\u{1b}[38;2;0;0;0m \u{1b}[38;2;165;43;0m$$$\u{1b}[38;2;247;76;0m@@@@\u{1b}[38;2;0;0;0m \u{1b}[38;2;247;76;0m@@@@\u{1b}[38;2;165;43;0m$\u{1b}[38;2;0;0;0m \u{1b}[38;2;165;43;0m$\u{1b}[38;2;247;76;0m@\u{1b}[38;2;0;0;0m   \u{1b}[0m\n\u{1b}[38;2;0;0;0m     \u{1b}[38;2;247;76;0m@\u{1b}[38;2;0;0;0m             \u{1b}[38;2;247;76;0m@\u{1b}[38;2;0;0;0m    \u{1b}[0m\n\u{1b}[38;2;0;0;0m                        \u{1b}[0m"
+    }
+
+    fn get_braille_ferris() -> Vec<&'static str> {
+        vec![
+            "⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀",
+            "⠀⠀⠀⠀⠀⠀⠀⣀⣰⣤⣿⣾⣷⣾⣦⣶⣀⡀⠀⠀⠀⠀⠀⠀",
+            "⢠⡀⣦⡀⠀⠰⣶⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣷⡆⠀⢠⣾⢀⡆",
+            "⠸⢷⣿⢇⣈⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣇⡸⣿⡿⠃",
+            "⠀⠀⠙⢶⣿⣿⣿⣿⣿⣿⠛⢈⣿⡟⠃⣻⣿⣿⣿⣿⣿⡃⠀⠀",
+            "⠀⠀⠀⠻⣟⢟⠛⠿⠿⢿⣿⣿⣍⣽⣿⡿⠿⠟⠋⡝⣽⠃⠀⠀",
+            "⠀⠀⠀⠀⠈⠢⠁⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠈⡐⠁⠀⠀⠀",
+            "⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀",
+        ]
+    }
+
+    fn get_ramp_ferris() -> Vec<&'static str> {
+        vec![
+            "                        ",
+            "        ++++++ +        ",
+            "  +   +++++++++++++  + +",
+            "++++ ++++++++++++++  ++ ",
+            "   +++++++# +  ++++++   ",
+            "   + ...++++ ++++. .+   ",
+            "     +             +    ",
+            "                        ",
+        ]
     }
 
     #[test]
@@ -297,7 +707,7 @@ mod tests {
         let result: Vec<String> = symbolize(
             image,
             0.03,
-            &vec![' ', '@', '$', '&'],
+            &[' ', '@', '$', '&'],
             FilterType::Nearest,
             false,
         )
@@ -313,7 +723,7 @@ mod tests {
         let result: String = symbolize(
             image,
             0.01,
-            &vec![' ', '@', '$', '&'],
+            &[' ', '@', '$', '&'],
             FilterType::Nearest,
             true,
         )
@@ -329,7 +739,7 @@ mod tests {
         let result: String = symbolize(
             image,
             0.03,
-            &vec![' ', '@', '$', '&'],
+            &[' ', '@', '$', '&'],
             FilterType::Nearest,
             false,
         )
@@ -345,7 +755,7 @@ mod tests {
         let result: Vec<u8> = symbolize(
             image,
             0.03,
-            &vec![' ', '@', '$', '&'],
+            &[' ', '@', '$', '&'],
             FilterType::Nearest,
             false,
         )
@@ -358,24 +768,656 @@ mod tests {
     #[test]
     fn returns_error_if_no_palette_passed() {
         let image = open("./test-data/ferris.png").unwrap();
-        let result = symbolize(image, 0.03, &vec![], FilterType::Nearest, false);
+        let result = symbolize(image, 0.03, &[], FilterType::Nearest, false);
 
-        assert!(result.is_err());
+        assert!(matches!(result, Err(SymbolizeError::EmptyPalette)));
         assert_eq!(
             result.err().unwrap().to_string(),
-            "pallete should contain at leasst one symbol, aborting"
+            "palette should contain at least one symbol"
         );
     }
 
     #[test]
     fn returns_error_if_scale_less_than_zero() {
         let image = open("./test-data/ferris.png").unwrap();
-        let result = symbolize(image, -0.03, &vec![' '], FilterType::Nearest, false);
+        let result = symbolize(image, -0.03, &[' '], FilterType::Nearest, false);
 
-        assert!(result.is_err());
+        assert!(matches!(result, Err(SymbolizeError::InvalidScale(_))));
         assert_eq!(
             result.err().unwrap().to_string(),
-            "scale should be > 0, aborting"
+            "scale should be > 0, got -0.03"
+        );
+    }
+
+    #[test]
+    fn returns_error_if_scale_is_zero_or_infinite() {
+        for scale in [0.0, f32::INFINITY] {
+            let image = open("./test-data/ferris.png").unwrap();
+            let result = symbolize(image, scale, &[' '], FilterType::Nearest, false);
+
+            assert!(
+                matches!(result, Err(SymbolizeError::InvalidScale(invalid)) if invalid == scale),
+                "{}",
+                scale
+            );
+        }
+    }
+
+    #[test]
+    fn returns_error_if_image_is_scaled_to_nothing() {
+        let image = open("./test-data/ferris.png").unwrap();
+        let result = symbolize(image, 0.0001, &[' '], FilterType::Nearest, false);
+
+        assert!(matches!(result, Err(SymbolizeError::ZeroSizedOutput)));
+    }
+
+    #[test]
+    fn leaves_transparent_cells_blank() {
+        let symbolize_ferris = |alpha: Option<AlphaOptions>| {
+            Symbolizer::new()
+                .scale(0.01)
+                .palette(&['.', '#'])
+                .filter_type(FilterType::Triangle)
+                .colorize(true)
+                .alpha(alpha)
+                .symbolize(open("./test-data/ferris.png").unwrap())
+                .unwrap()
+        };
+        let blank = Cell {
+            symbol: ' ',
+            fg: None,
+            bg: None,
+        };
+
+        let transparent = symbolize_ferris(Some(AlphaOptions::default()));
+        assert_eq!(transparent.cells[0][0], blank);
+        assert!(!transparent.to_ansi_rows()[0].starts_with('\x1b'));
+        assert!(transparent
+            .cells
+            .iter()
+            .flatten()
+            .any(|cell| *cell != blank));
+
+        let matted = symbolize_ferris(Some(AlphaOptions {
+            matte: Some(Rgb([255, 255, 255])),
+            ..Default::default()
+        }));
+        assert!(matted.cells.iter().flatten().all(|cell| *cell != blank));
+
+        let opaque = symbolize_ferris(None);
+        assert_eq!(opaque.cells[0][0].fg, Some(Rgb([0, 0, 0])));
+    }
+
+    #[test]
+    fn picks_palette_colors_from_opaque_pixels() {
+        // a red square in the corner of a transparent image storing blue
+        let image = RgbaImage::from_fn(8, 8, |x, y| {
+            if x < 2 && y < 2 {
+                Rgba([255, 0, 0, 255])
+            } else {
+                Rgba([0, 0, 255, 0])
+            }
+        });
+        let symbolize_image = |image: RgbaImage| {
+            Symbolizer::new()
+                .cell_aspect_ratio(1.0)
+                .palette(&['.', '#'])
+                .alpha(Some(AlphaOptions::default()))
+                .symbolize(DynamicImage::ImageRgba8(image))
+                .unwrap()
+        };
+
+        let result = symbolize_image(image.clone());
+        assert_eq!(result.legend, vec![('#', Rgb([255, 0, 0]))]);
+        assert_eq!(result.cells[0][0], Cell::new('#', Rgb([255, 0, 0]), None));
+        assert_eq!(result.cells[7][7].symbol, ' ');
+
+        let mut transparent = image;
+        transparent.pixels_mut().for_each(|pixel| pixel.0[3] = 0);
+        let result = symbolize_image(transparent);
+        assert!(result.legend.is_empty());
+        assert!(result.cells.iter().flatten().all(|cell| cell.symbol == ' '));
+    }
+
+    #[test]
+    fn renders_braille_ferris() {
+        let result = symbolize_ferris_with_mode(
+            0.01,
+            ColorDepth::None,
+            Mode::Braille(BrailleOptions {
+                threshold: 32,
+                ..Default::default()
+            }),
+        );
+
+        assert_eq!(result, get_braille_ferris());
+    }
+
+    #[test]
+    fn braille_mode_has_same_dimensions_as_palette_mode() {
+        let palette_result: Vec<String> = symbolize(
+            open("./test-data/ferris.png").unwrap(),
+            0.03,
+            &[' ', '@'],
+            FilterType::Nearest,
+            false,
+        )
+        .unwrap()
+        .into();
+        let braille_result = symbolize_ferris_with_mode(
+            0.03,
+            ColorDepth::None,
+            Mode::Braille(BrailleOptions::default()),
+        );
+
+        assert_eq!(braille_result.len(), palette_result.len());
+        assert_eq!(
+            braille_result[0].chars().count(),
+            palette_result[0].chars().count()
+        );
+    }
+
+    #[test]
+    fn half_block_mode_has_same_dimensions_as_palette_mode() {
+        let result = symbolize_ferris_with_mode(0.03, ColorDepth::None, Mode::HalfBlock);
+
+        assert_eq!(result.len(), get_ferris().len());
+        assert_eq!(result[0].chars().count(), get_ferris()[0].chars().count());
+    }
+
+    #[test]
+    fn renders_colorized_half_blocks_with_both_colors() {
+        let result = symbolize_ferris_with_mode(0.005, ColorDepth::TrueColor, Mode::HalfBlock);
+
+        assert_eq!(
+            result[1],
+            "\u{1b}[48;2;247;76;0;38;2;247;76;0m▀\u{1b}[38;2;0;0;0m▀▀\u{1b}[38;2;247;76;0m▀▀▀▀▀▀\u{1b}[38;2;0;0;0m▀\u{1b}[38;2;247;76;0m▀\u{1b}[38;2;0;0;0m▀\u{1b}[0m"
+        );
+    }
+
+    #[test]
+    fn block_modes_have_same_dimensions_as_palette_mode() {
+        for mode in [Mode::Quadrant, Mode::Sextant, Mode::Octant] {
+            let result = symbolize_ferris_with_mode(0.03, ColorDepth::TrueColor, mode);
+
+            assert_eq!(result.len(), get_ferris().len());
+        }
+    }
+
+    #[test]
+    fn renders_ferris_with_luminance_ramp() {
+        let result: Vec<String> = Symbolizer::new()
+            .scale(0.01)
+            .palette(&[' ', '.', '+', '#'])
+            .mode(Mode::Ramp(RampOptions::default()))
+            .symbolize(open("./test-data/ferris.png").unwrap())
+            .unwrap()
+            .into();
+
+        assert_eq!(result, get_ramp_ferris());
+    }
+
+    #[test]
+    fn returns_error_if_no_palette_passed_for_ramp() {
+        let image = open("./test-data/ferris.png").unwrap();
+        let result = Symbolizer::new()
+            .scale(0.03)
+            .palette(&[])
+            .mode(Mode::Ramp(RampOptions::default()))
+            .symbolize(image);
+
+        assert!(matches!(result, Err(SymbolizeError::EmptyPalette)));
+    }
+
+    #[test]
+    fn matches_palette_colors_with_chosen_metric() {
+        for metric in [
+            DistanceMetric::Euclidean,
+            DistanceMetric::Redmean,
+            DistanceMetric::Cie76,
+            DistanceMetric::Ciede2000,
+        ] {
+            let result: Vec<String> = Symbolizer::new()
+                .scale(0.03)
+                .palette(&[' ', '@', '$', '&'])
+                .metric(metric)
+                .symbolize(open("./test-data/ferris.png").unwrap())
+                .unwrap()
+                .into();
+
+            // ferris consists of flat colors, so any sane metric matches them the same way
+            assert_eq!(result, get_ferris());
+        }
+    }
+
+    #[test]
+    fn chooses_palette_colors_with_quantizer() {
+        for quantizer in [Quantizer::MedianCut, Quantizer::KMeans, Quantizer::Octree] {
+            let result: Vec<String> = Symbolizer::new()
+                .scale(0.03)
+                .palette(&[' ', '@', '$', '&'])
+                .mode(Mode::Palette(PaletteOptions {
+                    quantizer,
+                    ..Default::default()
+                }))
+                .symbolize(open("./test-data/ferris.png").unwrap())
+                .unwrap()
+                .into();
+
+            assert_eq!(result, get_ferris());
+        }
+    }
+
+    fn symbolize_dithered_ferris(dither: Dither, metric: DistanceMetric) -> Vec<String> {
+        // smooth scaling gives the in-between colors of the edges something to dither
+        Symbolizer::new()
+            .scale(0.01)
+            .palette(&[' ', '.', '+', '#'])
+            .filter_type(FilterType::Triangle)
+            .mode(Mode::Palette(PaletteOptions {
+                dither,
+                ..Default::default()
+            }))
+            .metric(metric)
+            .symbolize(open("./test-data/ferris.png").unwrap())
+            .unwrap()
+            .into()
+    }
+
+    fn symbolize_dithered_gradient(dither: Dither) -> Vec<String> {
+        // black and white are the most used colors, the gray ramp between them is dithered
+        let image = RgbImage::from_fn(16, 8, |x, y| match y {
+            0..=2 if x < 8 => Rgb([0, 0, 0]),
+            0..=2 => Rgb([255, 255, 255]),
+            _ => Rgb([64 + x as u8 * 8; 3]),
+        });
+
+        Symbolizer::new()
+            .cell_aspect_ratio(1.0)
+            .palette(&[' ', '#'])
+            .mode(Mode::Palette(PaletteOptions {
+                dither,
+                ..Default::default()
+            }))
+            .symbolize(DynamicImage::ImageRgb8(image))
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn renders_ferris_with_each_dithering_algorithm() {
+        let cases = [
+            (
+                Dither::FloydSteinberg,
+                vec![
+                    "        +++++++++       ",
+                    "  +  +++#+.####+++++++++",
+                    "++#+++###..####...# +#+#",
+                    "###+####..######.###+.# ",
+                    "  +#####.##+##+#.####+++",
+                    "  +#+#+###+##+###++##+++",
+                    "   ++++   +  ++  +++++++",
+                    "   +++++     +   +++++++",
+                ],
+            ),
+            (
+                Dither::Atkinson,
+                vec![
+                    "        +++++++++       ",
+                    "  +  +++#######+++++++++",
+                    "++#+++#########..#+++#++",
+                    "###+###########....####+",
+                    " ++########+##+#.#.##+++",
+                    "  +##++#####+####++##+ +",
+                    "  + ++++   + +   +++++++",
+                    "     +++    +    +++++++",
+                ],
+            ),
+            (
+                Dither::JarvisJudiceNinke,
+                vec![
+                    "        +++++++++       ",
+                    "  +  +++########++++++++",
+                    "++#+++############+++#++",
+                    "###+##############.####+",
+                    " ++########+##+######+++",
+                    "  +##++###+#+####++##+ +",
+                    "    +++            +++++",
+                    "    ++++           + +++",
+                ],
+            ),
+            (
+                Dither::Sierra,
+                vec![
+                    "        +++++++++       ",
+                    "  +  +++########++++ +++",
+                    "++#+++############+++#++",
+                    "###+#############..####+",
+                    " ++########+##+######+++",
+                    "  +##++###+#+####++##+++",
+                    "    ++++          ++++++",
+                    "    ++++          ++ +++",
+                ],
+            ),
+            (
+                Dither::Bayer2,
+                vec![
+                    "        ++++++++        ",
+                    "  +  ++++#######+++ ++++",
+                    "++#+++###......###+++#++",
+                    "+###+#....##.###..#####+",
+                    "+++###...##+##+#..###++ ",
+                    "  +###############++#++ ",
+                    "   +++++++++++++++++++  ",
+                    "     ++           ++    ",
+                ],
+            ),
+            (
+                Dither::Bayer4,
+                vec![
+                    "        ++++++++        ",
+                    "  +  ++++#######+++ ++++",
+                    "++#+++###......###+++#++",
+                    "+###+#....##.###..#####+",
+                    "+++###...##+##+#..###++ ",
+                    "  +###############++#++ ",
+                    "   +++++++++++++++++++  ",
+                    "     ++           ++    ",
+                ],
+            ),
+            (
+                Dither::Bayer8,
+                vec![
+                    "        ++++++++        ",
+                    "  +  ++++#######+++ ++++",
+                    "++#+++###......###+++#++",
+                    "+###+#....##.###..#####+",
+                    "+++###...##+##+#..###++ ",
+                    "  +###############++#++ ",
+                    "   +++++++++++++++++++  ",
+                    "     ++           ++    ",
+                ],
+            ),
+        ];
+
+        for (dither, expected) in cases {
+            assert_eq!(
+                symbolize_dithered_ferris(dither, DistanceMetric::Manhattan),
+                expected,
+                "{:?}",
+                dither
+            );
+        }
+    }
+
+    #[test]
+    fn renders_gradient_with_each_bayer_matrix() {
+        let cases = [
+            (
+                Dither::Bayer2,
+                [
+                    "# # # # # # ####",
+                    "     # # # # # #",
+                    "# # # # # # ####",
+                    "     # # # # # #",
+                    "# # # # # # ####",
+                ],
+            ),
+            (
+                Dither::Bayer4,
+                [
+                    "# # # # ### ####",
+                    "   #   # # # # #",
+                    "# # # # # ### ##",
+                    " #   # # # # # #",
+                    "# # # # ### ####",
+                ],
+            ),
+            (
+                Dither::Bayer8,
+                [
+                    "# # # # # # ####",
+                    "   #   # # # # #",
+                    "# # # # # ### ##",
+                    " #   # # # # # #",
+                    "# # # # ### ####",
+                ],
+            ),
+        ];
+
+        for (dither, expected) in cases {
+            let result = symbolize_dithered_gradient(dither);
+
+            // the black and white rows already match the palette colors, so they aren't dithered
+            assert_eq!(result[..3], ["        ########"; 3], "{:?}", dither);
+            assert_eq!(result[3..], expected, "{:?}", dither);
+        }
+    }
+
+    #[test]
+    fn dithers_in_color_space_of_metric() {
+        assert_eq!(
+            symbolize_dithered_ferris(Dither::FloydSteinberg, DistanceMetric::Cie76),
+            vec![
+                "        +++++++++       ",
+                " ++  +++#######+#    +++",
+                "++#+++############+++#+#",
+                "####+...##########.####+",
+                "  +##...##++#++######+++",
+                "  +##+###########+#+#+++",
+                "    +++            +++++",
+                "    +++           ++++++",
+            ]
+        );
+    }
+
+    #[test]
+    fn fits_ferris_into_budget() {
+        let image = open("./test-data/ferris.png").unwrap();
+        let dimensions = (image.width(), image.height());
+
+        for (columns, rows) in [(Some(80), None), (None, Some(10)), (Some(80), Some(10))] {
+            let scale = get_scale_to_fit(dimensions, columns, rows, DEFAULT_CELL_ASPECT_RATIO);
+            let result: Vec<String> = symbolize(
+                image.clone(),
+                scale,
+                &[' ', '@'],
+                FilterType::Nearest,
+                false,
+            )
+            .unwrap()
+            .into();
+
+            assert!(result.len() <= rows.unwrap_or(u32::MAX) as usize);
+            assert!(result[0].chars().count() <= columns.unwrap_or(u32::MAX) as usize);
+            // the limiting dimension is filled completely
+            assert!(
+                result.len() == rows.unwrap_or(0) as usize
+                    || result[0].chars().count() == columns.unwrap_or(0) as usize
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_original_size_without_budget() {
+        assert_eq!(
+            get_scale_to_fit((100, 50), None, None, DEFAULT_CELL_ASPECT_RATIO),
+            1.0
+        );
+    }
+
+    #[test]
+    fn stretches_columns_by_cell_aspect_ratio() {
+        for (cell_aspect_ratio, columns) in [(1.0, 36), (2.2, 79)] {
+            let result: Vec<String> = Symbolizer::new()
+                .scale(0.03)
+                .palette(&[' ', '@', '$', '&'])
+                .cell_aspect_ratio(cell_aspect_ratio)
+                .symbolize(open("./test-data/ferris.png").unwrap())
+                .unwrap()
+                .into();
+
+            assert_eq!(result.len(), get_ferris().len());
+            assert_eq!(result[0].chars().count(), columns);
+        }
+    }
+
+    #[test]
+    fn returns_error_if_cell_aspect_ratio_is_not_positive() {
+        let result = Symbolizer::new()
+            .scale(0.03)
+            .palette(&[' '])
+            .cell_aspect_ratio(0.0)
+            .symbolize(open("./test-data/ferris.png").unwrap());
+
+        assert!(matches!(
+            result,
+            Err(SymbolizeError::InvalidCellAspectRatio(_))
+        ));
+    }
+
+    #[test]
+    fn returns_error_if_glyph_cell_size_is_zero() {
+        let result = Symbolizer::new()
+            .scale(0.03)
+            .palette(&[' ', '#'])
+            .mode(Mode::Glyph(GlyphOptions {
+                cell_height: 0,
+                ..GlyphOptions::new(Font::builtin().clone())
+            }))
+            .symbolize(open("./test-data/ferris.png").unwrap());
+
+        assert!(matches!(
+            result,
+            Err(SymbolizeError::InvalidOption {
+                name: "cell size",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn returns_error_if_glyph_cells_overflow_image_size() {
+        let result = Symbolizer::new()
+            .scale(0.03)
+            .palette(&[' ', '#'])
+            .mode(Mode::Glyph(GlyphOptions {
+                cell_width: u32::MAX,
+                ..GlyphOptions::new(Font::builtin().clone())
+            }))
+            .symbolize(open("./test-data/ferris.png").unwrap());
+
+        assert!(matches!(
+            result,
+            Err(SymbolizeError::InvalidOption { name: "cell size", value }) if value == "4294967295x16"
+        ));
+    }
+
+    #[test]
+    fn maps_colors_to_color_depth() {
+        for (color_depth, orange) in [
+            (ColorDepth::Xterm256, "\u{1b}[38;5;202m@"),
+            (ColorDepth::Ansi16, "\u{1b}[91m@"),
+            (ColorDepth::Ansi8, "\u{1b}[31m@"),
+        ] {
+            let result: String = Symbolizer::new()
+                .scale(0.01)
+                .palette(&[' ', '@', '$', '&'])
+                .color_depth(color_depth)
+                .symbolize(open("./test-data/ferris.png").unwrap())
+                .unwrap()
+                .into();
+
+            assert!(result.contains(orange), "{:?}", color_depth);
+            assert!(!result.contains("38;2;"), "{:?}", color_depth);
+        }
+    }
+
+    #[test]
+    fn renders_solid_mosaic() {
+        let result: Vec<String> = Symbolizer::new()
+            .scale(0.01)
+            .palette(&[' ', '@', '$', '&'])
+            .color_depth(ColorDepth::Ansi8)
+            .background(Background::Solid)
+            .symbolize(open("./test-data/ferris.png").unwrap())
+            .unwrap()
+            .into();
+
+        assert_eq!(
+            result[1],
+            "\u{1b}[40m        \u{1b}[41m      \u{1b}[40m \u{1b}[41m \u{1b}[40m        \u{1b}[0m"
+        );
+    }
+
+    #[test]
+    fn sets_colors_only_when_they_change() {
+        let image = open("./test-data/ferris.png").unwrap();
+        let result: String = symbolize(
+            image,
+            0.01,
+            &[' ', '@', '$', '&'],
+            FilterType::Nearest,
+            true,
+        )
+        .unwrap()
+        .into();
+        let per_cell = get_per_cell_colorized_ferris();
+        let strip = |text: &str| {
+            text.split('\u{1b}')
+                .map(|part| part.split_once('m').map_or(part, |(_, rest)| rest))
+                .collect::<String>()
+        };
+
+        assert_eq!(strip(&result), strip(per_cell));
+        assert!(result.len() * 4 < per_cell.len());
+    }
+
+    #[test]
+    fn saves_image_in_format_of_extension() {
+        let result = symbolize(
+            open("./test-data/ferris.png").unwrap(),
+            0.01,
+            &[' ', '@', '$', '&'],
+            FilterType::Nearest,
+            true,
+        )
+        .unwrap();
+        let directory = std::env::temp_dir();
+
+        for extension in ["png", "jpg", "bmp"] {
+            let path = directory.join(format!("symbolize-ferris.{}", extension));
+            result.save_image(&path, &RasterOptions::default()).unwrap();
+
+            assert_eq!(image::image_dimensions(&path).unwrap(), (8 * 24, 16 * 8));
+        }
+        assert!(matches!(
+            result.save_image(directory.join("ferris.unknown"), &RasterOptions::default()),
+            Err(SymbolizeError::Encode(_))
+        ));
+    }
+
+    #[test]
+    fn exposes_cell_grid() {
+        let image = open("./test-data/ferris.png").unwrap();
+        let result = symbolize(
+            image,
+            0.01,
+            &[' ', '@', '$', '&'],
+            FilterType::Nearest,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(result.cells.len(), 8);
+        assert_eq!(
+            result.cells[1][8],
+            Cell {
+                symbol: '@',
+                fg: Some(Rgb([247, 76, 0])),
+                bg: None,
+            }
         );
+        assert_eq!(result.color_depth, ColorDepth::None);
     }
 }
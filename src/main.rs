@@ -1,10 +1,29 @@
 use clap::Parser;
-use image::{imageops::FilterType, open, ImageError};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    execute, queue, terminal,
+};
+use glob::{glob, Pattern};
+use image::{imageops::FilterType, open, Delay, DynamicImage, Frame, ImageFormat, Rgb};
+use rayon::{prelude::*, ThreadPoolBuilder};
 use std::{
-    io::{Error, ErrorKind},
+    collections::{HashMap, HashSet},
+    env,
+    ffi::OsStr,
+    fs,
+    io::{self, Cursor, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
     process,
+    time::{Duration, Instant},
+};
+use symbolize::{
+    get_scale_to_fit, load_frames, open_frames, to_asciicast, AlphaOptions, AsciicastOptions,
+    Background, BrailleOptions, ColorDepth, DistanceMetric, Dither, Font, GlyphMatching,
+    GlyphOptions, HtmlOptions, Mode, PaletteOptions, Quantizer, RampCurve, RampOptions,
+    RasterOptions, SvgOptions, SymbolizeError, SymbolizedFrame, SymbolizedFrames, Symbolizer,
+    DEFAULT_CELL_ASPECT_RATIO,
 };
-use symbolize::symbolize;
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
@@ -15,17 +34,86 @@ use symbolize::symbolize;
     long_about = None
 )]
 struct Args {
-    /// Path to the original picture
-    #[clap(value_parser)]
-    path: String,
+    /// Paths to the original pictures, - reads one from stdin.
+    /// Directories and glob patterns (quoted from the shell) are expanded with --output-dir
+    #[clap(value_parser, required = true)]
+    paths: Vec<String>,
 
-    /// Defines scale of symbolized picture relatively to the original
+    /// File the output is written to instead of stdout
+    #[clap(short, long, value_parser)]
+    output: Option<String>,
+
+    /// Defines scale of symbolized picture relatively to the original.
+    /// Ignored when --width, --height or --fit is set
     #[clap(long, value_parser, default_value_t = 1.0)]
     scale: f32,
 
-    /// Defines symbols that will be used to fill the picture (in priority order)
+    /// Maximum width of the result in columns, the aspect ratio is preserved
+    #[clap(long, value_parser)]
+    width: Option<u32>,
+
+    /// Maximum height of the result in rows, the aspect ratio is preserved
+    #[clap(long, value_parser)]
+    height: Option<u32>,
+
+    /// Fit the result into the terminal, --width and --height override its dimensions
+    #[clap(long, action, default_value_t = false)]
+    fit: bool,
+
+    /// Height of a character cell divided by its width, e.g. 1 for square cells
+    #[clap(long, value_parser, default_value_t = DEFAULT_CELL_ASPECT_RATIO)]
+    cell_aspect_ratio: f32,
+
+    /// Defines symbols that will be used to fill the picture (in priority order).
+    /// By default " .:-=+*#%@", from the lightest to the densest symbol
     #[clap(short, long, value_parser)]
-    palette: String,
+    palette: Option<String>,
+
+    /// Rendering mode. One of: palette, ramp, glyph, braille, half_block, quadrant, sextant, octant
+    #[clap(short, long, value_parser, default_value = "palette")]
+    mode: String,
+
+    /// Color distance used to match pixels with the palette colors.
+    /// One of: manhattan, euclidean, redmean, cie76, ciede2000
+    #[clap(long, value_parser, default_value = "manhattan")]
+    metric: String,
+
+    /// Method of choosing the palette colors from the image.
+    /// One of: most_used, median_cut, k_means, octree
+    #[clap(long, value_parser, default_value = "most_used")]
+    quantizer: String,
+
+    /// Luminance (0-255) starting from which a braille dot is raised
+    #[clap(long, value_parser, default_value_t = 128)]
+    threshold: u8,
+
+    /// Raise braille dots for the dark pixels instead of the bright ones,
+    /// treat the ramp palette as ordered from light to dark, or treat dark pixels as glyph ink
+    #[clap(long, action, default_value_t = false)]
+    invert: bool,
+
+    /// Lightness curve of the ramp mode. One of: linear, gamma:<value>,
+    /// or comma-separated levels from 0 to 1, e.g. 0,0.8,1
+    #[clap(long, value_parser, default_value = "linear")]
+    curve: String,
+
+    /// Path to a monospace TTF, OTF, BDF or PSF font used by the glyph mode and the png format
+    #[clap(long, value_parser)]
+    font: Option<String>,
+
+    /// Size of the pixel block matched against a single glyph, or of a cell of the png format,
+    /// in <width>x<height> format
+    #[clap(long, value_parser, default_value = "8x16")]
+    cell_size: String,
+
+    /// Glyph matching method. One of: least_squares, ssim
+    #[clap(long, value_parser, default_value = "least_squares")]
+    matching: String,
+
+    /// Dithering of the palette and braille modes. One of: none, floyd_steinberg, atkinson,
+    /// jarvis_judice_ninke, sierra, bayer2, bayer4, bayer8
+    #[clap(long, value_parser, default_value = "none")]
+    dither: String,
 
     /// Filter type. One of: nearest, triangle, catmull_rom, gaussian, lanczos3.
     /// More about differences: https://docs.rs/image/latest/image/imageops/enum.FilterType.html
@@ -33,43 +121,641 @@ struct Args {
     filter: String,
 
     /// Flag that shows should output be colorized for a terminal or not.
-    /// Not recommended to use it with anything but terminals with rgb support
+    /// The color depth is detected by COLORTERM and TERM unless --color-depth is set
     #[clap(short, long, action, default_value_t = false)]
     colorize: bool,
+
+    /// Colors of the output. One of: auto, truecolor, 256, 16, 8, none.
+    /// Colorizes the output even without --colorize unless it's none.
+    /// The json format is colorized by default
+    #[clap(long, value_parser)]
+    color_depth: Option<String>,
+
+    /// Put the colors to the cell background. One of: none, fill (default glyph color),
+    /// contrast (black or white glyphs), solid (spaces on colored cells).
+    /// Colorizes the output even without --colorize unless it's none
+    #[clap(long, value_parser, default_value = "none")]
+    background: String,
+
+    /// Opacity (0-255) below which a cell is left blank, 0 keeps every cell
+    #[clap(long, value_parser, default_value_t = AlphaOptions::default().threshold)]
+    alpha_threshold: u8,
+
+    /// Color the transparent pixels are composited onto, in #rrggbb format
+    #[clap(long, value_parser)]
+    matte: Option<String>,
+
+    /// Output format. One of: ansi (text for a terminal), html (a page with a colored <pre>),
+    /// svg (an image with a text or a rect for every cell), png (an image drawn with a bitmap font
+    /// or --font, a cell takes --cell-size pixels), json (the dimensions, the legend of the palette
+    /// colors and the symbol and colors of every cell), asciicast (an asciinema v2 recording
+    /// of the frames of an animated GIF, APNG or WebP image).
+    /// Guessed from the extension of --output when not set, any image extension gives png
+    /// in the format of the extension
+    #[clap(long, value_parser)]
+    format: Option<String>,
+
+    /// Output only the <pre> element instead of a complete page (html format)
+    #[clap(long, action, default_value_t = false)]
+    fragment: bool,
+
+    /// Font family of the symbols (html and svg formats)
+    #[clap(long, value_parser, default_value = "monospace")]
+    font_family: String,
+
+    /// Font size of the symbols in pixels (html and svg formats)
+    #[clap(long, value_parser, default_value_t = 12.0)]
+    font_size: f32,
+
+    /// Size of a cell in pixels in <width>x<height> format (svg format).
+    /// By default 0.6 of the font size wide and stretched by --cell-aspect-ratio
+    #[clap(long, value_parser)]
+    svg_cell_size: Option<String>,
+
+    /// Play an animated GIF, APNG or WebP image in place with its frame delays (ansi format),
+    /// shrunk to fit the terminal. Stops on Ctrl-C, q or Esc
+    #[clap(long, action, default_value_t = false)]
+    play: bool,
+
+    /// Maximum frame rate of --play and the asciicast format, faster frames are dropped.
+    /// 0 doesn't limit it
+    #[clap(long, value_parser, default_value_t = 30.0)]
+    max_fps: f32,
+
+    /// How many times --play or the asciicast format shows the animation.
+    /// When not set --play loops until stopped and the recording shows it once
+    #[clap(long, value_parser)]
+    loops: Option<u32>,
+
+    /// Title of the recording (asciicast format)
+    #[clap(long, value_parser)]
+    title: Option<String>,
+
+    /// Convert every picture of the paths into this directory, mirroring the trees
+    /// of the directories and glob patterns. A failed picture doesn't stop the others
+    #[clap(long, value_parser)]
+    output_dir: Option<String>,
+
+    /// Extension of the files of --output-dir, by default the one of the format.
+    /// Guesses the format like the extension of --output
+    #[clap(long, value_parser)]
+    extension: Option<String>,
+
+    /// Amount of pictures converted in parallel with --output-dir, 0 uses all of the cores
+    #[clap(long, value_parser, default_value_t = 0)]
+    jobs: usize,
 }
 
-fn main() -> Result<(), ImageError> {
+/// Path that reads the image from stdin
+const STDIN_PATH: &str = "-";
+
+/// Symbols that make a path a glob pattern
+const GLOB_SYMBOLS: [char; 3] = ['*', '?', '['];
+
+/// Pictures of a batch that failed to convert with their errors
+type Failures = Vec<(PathBuf, SymbolizeError)>;
+
+/// How the result is written to stdout or to --output
+enum Format {
+    Ansi,
+    Html(HtmlOptions),
+    Svg(SvgOptions),
+    Png(RasterOptions),
+    #[cfg(feature = "serde")]
+    Json,
+    Asciicast(AsciicastOptions),
+}
+
+fn main() {
     let args = Args::parse();
-    let filter_type_wrapper: FilterTypeWrapper = args.filter.try_into()?;
-    let palette: Vec<char> = args.palette.chars().collect();
-
-    let result = symbolize(
-        open(args.path)?,
-        args.scale,
-        &palette,
-        filter_type_wrapper.0,
-        args.colorize,
-    );
+    let Some(output_dir) = &args.output_dir else {
+        if let Err(e) = run(&args) {
+            eprintln!("{}, aborting", e);
+            process::exit(get_exit_code(&e));
+        }
+        return;
+    };
 
-    match result {
+    match run_batch(&args, Path::new(output_dir)) {
         Err(e) => {
             eprintln!("{}", e);
-            process::exit(1);
+            process::exit(get_exit_code(&e));
+        }
+        // the failures are already reported by the summary
+        Ok(failures) => {
+            if let Some((_, e)) = failures.first() {
+                process::exit(get_exit_code(e));
+            }
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<(), SymbolizeError> {
+    if args.paths.len() > 1 {
+        return Err(SymbolizeError::MissingOption(
+            "output dir for several paths",
+        ));
+    }
+
+    let format = get_format(args)?;
+    let symbolizer = get_symbolizer(args, &format)?;
+    let path = Path::new(&args.paths[0]);
+
+    if args.play {
+        // the frames are drawn over each other, so they can only be shown in a terminal
+        if !matches!(format, Format::Ansi) || args.output.is_some() {
+            return Err(SymbolizeError::InvalidOption {
+                name: "output of --play",
+                value: args.output.clone().unwrap_or_else(|| get_format_name(args)),
+            });
         }
-        Ok(result) => {
-            for line in Into::<Vec<String>>::into(result) {
-                println!("{}", line)
+
+        let frames = open_image_frames(path, io::stdin())?;
+        let symbolizer = match frames.first() {
+            Some(frame) => {
+                fit_to_terminal(symbolizer, frame.buffer().dimensions(), terminal::size()?)
+            }
+            None => symbolizer,
+        };
+
+        return play(get_frames(&symbolizer, args, frames)?, args.loops);
+    }
+
+    convert(
+        &symbolizer,
+        &format,
+        args,
+        path,
+        args.output.as_deref().map(Path::new),
+    )
+}
+
+/// Converts every picture of the paths into the output directory, a failed picture doesn't stop the others.
+/// Prints a summary and returns the failed pictures
+fn run_batch(args: &Args, output_dir: &Path) -> Result<Failures, SymbolizeError> {
+    if args.play || args.output.is_some() {
+        return Err(SymbolizeError::InvalidOption {
+            name: "output with --output-dir",
+            value: args.output.clone().unwrap_or_else(|| "--play".to_string()),
+        });
+    }
+
+    let format = get_format(args)?;
+    let symbolizer = get_symbolizer(args, &format)?;
+    let extension = get_extension(args, &format);
+    let (inputs, mut failures) = get_inputs(&args.paths, &extension)?;
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(args.jobs)
+        .build()
+        .map_err(io::Error::other)?;
+
+    let results: Vec<Result<(), SymbolizeError>> = pool.install(|| {
+        inputs
+            .par_iter()
+            .map(|(input, relative)| {
+                let output = output_dir.join(relative);
+                if let Some(parent) = output.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+
+                convert(&symbolizer, &format, args, input, Some(&output))
+            })
+            .collect()
+    });
+
+    let total = inputs.len() + failures.len();
+    failures.extend(
+        inputs
+            .into_iter()
+            .zip(results)
+            .filter_map(|((input, _), result)| result.err().map(|e| (input, e))),
+    );
+    for (input, e) in &failures {
+        eprintln!("{}: {}", input.display(), e);
+    }
+    eprintln!(
+        "converted {} of {} pictures into {}",
+        total - failures.len(),
+        total,
+        output_dir.display()
+    );
+
+    Ok(failures)
+}
+
+fn get_symbolizer(args: &Args, format: &Format) -> Result<Symbolizer, SymbolizeError> {
+    let (columns, rows) = get_budget(args)?;
+    let filter_type_wrapper: FilterTypeWrapper = args.filter.clone().try_into()?;
+
+    let mut symbolizer = Symbolizer::new();
+    if let Some(palette) = &args.palette {
+        symbolizer = symbolizer.palette(&palette.chars().collect::<Vec<_>>());
+    }
+
+    Ok(symbolizer
+        .scale(args.scale)
+        .fit(columns, rows)
+        .color_depth(get_color_depth(args, format)?)
+        .background(get_background(&args.background)?)
+        .mode(get_mode(args)?)
+        .metric(get_metric(&args.metric)?)
+        .cell_aspect_ratio(args.cell_aspect_ratio)
+        .alpha(Some(get_alpha(args)?))
+        .filter_type(filter_type_wrapper.0))
+}
+
+/// Symbolizes the picture and writes it in the format to the output, or to stdout without one
+fn convert(
+    symbolizer: &Symbolizer,
+    format: &Format,
+    args: &Args,
+    input: &Path,
+    output: Option<&Path>,
+) -> Result<(), SymbolizeError> {
+    // animations are decoded frame by frame, so the still image is only opened by the other formats
+    let symbolize = || symbolizer.symbolize(open_image(input, io::stdin())?);
+    let bytes = match format {
+        Format::Ansi => format!("{}\n", String::from(symbolize()?)).into_bytes(),
+        Format::Html(options) => format!("{}\n", symbolize()?.to_html(options)).into_bytes(),
+        Format::Svg(options) => symbolize()?.to_svg(options).into_bytes(),
+        Format::Png(options) => {
+            let result = symbolize()?;
+            // the image is saved in the format of the extension when there is a known one
+            if let Some(path) = output {
+                if ImageFormat::from_path(path).is_ok() {
+                    return result.save_image(path, options);
+                }
+            }
+
+            let mut png = Cursor::new(vec![]);
+            DynamicImage::ImageRgba8(result.to_image(options)?)
+                .write_to(&mut png, ImageFormat::Png)
+                .map_err(SymbolizeError::Encode)?;
+            png.into_inner()
+        }
+        #[cfg(feature = "serde")]
+        Format::Json => format!("{}\n", symbolize()?.to_json()).into_bytes(),
+        Format::Asciicast(options) => {
+            let frames = open_image_frames(input, io::stdin())?;
+            let frames = get_frames(symbolizer, args, frames)?.collect::<Result<Vec<_>, _>>()?;
+            to_asciicast(&frames, options).into_bytes()
+        }
+    };
+
+    write_output(&bytes, output)
+}
+
+/// Decodes the image at the path, or the one read from stdin for -
+fn open_image(path: &Path, stdin: impl Read) -> Result<DynamicImage, SymbolizeError> {
+    if path != Path::new(STDIN_PATH) {
+        return Ok(open(path)?);
+    }
+
+    let (bytes, format) = read_image(stdin)?;
+    Ok(image::load_from_memory_with_format(&bytes, format)?)
+}
+
+/// Decodes every frame of the image at the path, or of the one read from stdin for -
+fn open_image_frames(path: &Path, stdin: impl Read) -> Result<Vec<Frame>, SymbolizeError> {
+    if path != Path::new(STDIN_PATH) {
+        return open_frames(path);
+    }
+
+    let (bytes, format) = read_image(stdin)?;
+    load_frames(Cursor::new(bytes), format)
+}
+
+/// Reads the whole image, its format is sniffed from the first bytes
+fn read_image(mut reader: impl Read) -> Result<(Vec<u8>, ImageFormat), SymbolizeError> {
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+    let format = image::guess_format(&bytes)?;
+
+    Ok((bytes, format))
+}
+
+fn write_output(output: &[u8], path: Option<&Path>) -> Result<(), SymbolizeError> {
+    match path {
+        Some(path) => fs::write(path, output)?,
+        None => io::stdout().lock().write_all(output)?,
+    }
+
+    Ok(())
+}
+
+/// Expands the paths into the pictures with their output paths relative to the output directory.
+/// The tree under a directory or under the fixed part of a glob pattern is mirrored,
+/// a separate picture is put at the top. Files other than pictures are skipped
+/// in directories and glob patterns, the entries that can't be read are returned as failures.
+/// Fails if two pictures would be written to the same output path
+fn get_inputs(
+    paths: &[String],
+    extension: &str,
+) -> Result<(Vec<(PathBuf, PathBuf)>, Failures), SymbolizeError> {
+    let (mut inputs, mut failures) = (vec![], vec![]);
+    for path in paths {
+        let (base, pattern) = if Path::new(path).is_dir() {
+            (
+                PathBuf::from(path),
+                format!("{}/**/*", Pattern::escape(path)),
+            )
+        } else if path.contains(GLOB_SYMBOLS) {
+            (get_glob_base(path), path.clone())
+        } else {
+            let input = PathBuf::from(path);
+            let relative = input.file_name().map(PathBuf::from).ok_or_else(|| {
+                SymbolizeError::InvalidOption {
+                    name: "path",
+                    value: path.clone(),
+                }
+            })?;
+            inputs.push((input, relative.with_extension(extension)));
+            continue;
+        };
+
+        let entries = glob(&pattern).map_err(|_| SymbolizeError::InvalidOption {
+            name: "glob pattern",
+            value: path.clone(),
+        })?;
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                // the rest of the pictures are still converted when a directory can't be read
+                Err(e) => {
+                    failures.push((e.path().to_path_buf(), io::Error::from(e).into()));
+                    continue;
+                }
+            };
+            if !entry.is_file() || ImageFormat::from_path(&entry).is_err() {
+                continue;
+            }
+
+            let relative = entry
+                .strip_prefix(&base)
+                .unwrap_or(&entry)
+                .with_extension(extension);
+            inputs.push((entry, relative));
+        }
+    }
+
+    // a picture matched by several paths is converted once, at the first place it's matched
+    let mut seen = HashSet::new();
+    inputs.retain(|(input, _)| seen.insert(input.clone()));
+
+    // the pictures are converted in parallel, so two of them can't be written to the same file
+    let mut outputs = HashMap::new();
+    for (input, relative) in &inputs {
+        if let Some(other) = outputs.insert(relative, input) {
+            return Err(SymbolizeError::InvalidOption {
+                name: "output path",
+                value: format!(
+                    "{} of both {} and {}",
+                    relative.display(),
+                    other.display(),
+                    input.display()
+                ),
+            });
+        }
+    }
+
+    Ok((inputs, failures))
+}
+
+/// Leading directories of the glob pattern without any wildcards
+fn get_glob_base(pattern: &str) -> PathBuf {
+    Path::new(pattern)
+        .components()
+        .take_while(|component| {
+            !component
+                .as_os_str()
+                .to_string_lossy()
+                .contains(GLOB_SYMBOLS)
+        })
+        .collect()
+}
+
+/// Extension of the files of --output-dir, by default the one of the format
+fn get_extension(args: &Args, format: &Format) -> String {
+    if let Some(extension) = &args.extension {
+        return extension.clone();
+    }
+
+    match format {
+        Format::Ansi => "txt",
+        Format::Html(_) => "html",
+        Format::Svg(_) => "svg",
+        Format::Png(_) => "png",
+        #[cfg(feature = "serde")]
+        Format::Json => "json",
+        Format::Asciicast(_) => "cast",
+    }
+    .to_string()
+}
+
+/// Symbolizes the frames lazily, the frames that would exceed --max-fps are dropped first
+fn get_frames(
+    symbolizer: &Symbolizer,
+    args: &Args,
+    frames: Vec<Frame>,
+) -> Result<SymbolizedFrames, SymbolizeError> {
+    symbolizer.symbolize_frames(drop_frames(frames, args.max_fps)?)
+}
+
+/// Drops the frames shown sooner than 1/max_fps after the previous kept one,
+/// their delays are added to it so the animation keeps its duration
+fn drop_frames(frames: Vec<Frame>, max_fps: f32) -> Result<Vec<Frame>, SymbolizeError> {
+    let invalid = || SymbolizeError::InvalidOption {
+        name: "max fps",
+        value: max_fps.to_string(),
+    };
+    if max_fps < 0.0 || max_fps.is_nan() {
+        return Err(invalid());
+    }
+    if max_fps == 0.0 {
+        return Ok(frames);
+    }
+    let min_delay = Duration::try_from_secs_f32(1.0 / max_fps).map_err(|_| invalid())?;
+
+    let mut kept: Vec<(Frame, Duration)> = vec![];
+    for frame in frames {
+        let delay = frame.delay().into();
+        match kept.last_mut() {
+            Some((_, kept_delay)) if *kept_delay < min_delay => *kept_delay += delay,
+            _ => kept.push((frame, delay)),
+        }
+    }
+
+    Ok(kept
+        .into_iter()
+        .map(|(frame, delay)| {
+            let (left, top) = (frame.left(), frame.top());
+            Frame::from_parts(
+                frame.into_buffer(),
+                left,
+                top,
+                Delay::from_saturating_duration(delay),
+            )
+        })
+        .collect())
+}
+
+/// Shrinks the result into the terminal so the frames can be drawn over each other without scrolling,
+/// a smaller scale or budget is kept
+fn fit_to_terminal(
+    symbolizer: Symbolizer,
+    image_size: (u32, u32),
+    (terminal_columns, terminal_rows): (u16, u16),
+) -> Symbolizer {
+    // one row is left for the cursor below the frame
+    let (terminal_columns, terminal_rows) = (
+        terminal_columns as u32,
+        (terminal_rows as u32).saturating_sub(1),
+    );
+    let options = symbolizer.options();
+
+    if options.columns.is_some() || options.rows.is_some() {
+        let columns = options
+            .columns
+            .map_or(terminal_columns, |c| c.min(terminal_columns));
+        let rows = options.rows.map_or(terminal_rows, |r| r.min(terminal_rows));
+        return symbolizer.fit(Some(columns), Some(rows));
+    }
+
+    let scale = get_scale_to_fit(
+        image_size,
+        Some(terminal_columns),
+        Some(terminal_rows),
+        options.cell_aspect_ratio,
+    );
+    if scale < options.scale {
+        symbolizer.fit(Some(terminal_columns), Some(terminal_rows))
+    } else {
+        symbolizer
+    }
+}
+
+/// Draws the frames over each other in raw mode, so the keys can stop the playback without a signal.
+/// The terminal is restored even if drawing fails
+fn play(frames: SymbolizedFrames, loops: Option<u32>) -> Result<(), SymbolizeError> {
+    let mut stdout = io::stdout().lock();
+    terminal::enable_raw_mode()?;
+    let played = execute!(stdout, cursor::Hide)
+        .map_err(SymbolizeError::from)
+        .and_then(|_| play_frames(&mut stdout, frames, loops));
+    let restored = execute!(stdout, cursor::Show)
+        .and_then(|_| stdout.write_all(b"\r\n"))
+        .and_then(|_| terminal::disable_raw_mode());
+
+    played.and(restored.map_err(SymbolizeError::from))
+}
+
+/// Symbolizes every frame while the first loop shows it, the next loops redraw the kept frames
+fn play_frames(
+    stdout: &mut impl Write,
+    frames: impl Iterator<Item = Result<SymbolizedFrame, SymbolizeError>>,
+    loops: Option<u32>,
+) -> Result<(), SymbolizeError> {
+    let loops = loops.unwrap_or(u32::MAX);
+    if loops == 0 {
+        return Ok(());
+    }
+
+    let mut drawn_rows = 0;
+    let mut deadline = Instant::now();
+    // waits for the delay of the previous frame, so the symbolizing time is a part of it
+    let mut show = |(frame, rows, delay): &(String, u16, Duration)| {
+        if is_stopped(deadline.saturating_duration_since(Instant::now()))? {
+            return Ok(true);
+        }
+
+        if drawn_rows > 1 {
+            queue!(stdout, cursor::MoveToPreviousLine(drawn_rows - 1))?;
+        } else {
+            queue!(stdout, cursor::MoveToColumn(0))?;
+        }
+        stdout.write_all(frame.as_bytes())?;
+        stdout.flush()?;
+        drawn_rows = *rows;
+        deadline = Instant::now() + *delay;
+
+        Ok::<_, SymbolizeError>(false)
+    };
+
+    let mut kept = vec![];
+    for frame in frames {
+        let frame = frame?;
+        // raw mode doesn't move the cursor to the start of a line on a line feed
+        let frame = (
+            frame.result.to_ansi_rows().join("\r\n"),
+            frame.result.cells.len() as u16,
+            frame.delay,
+        );
+        if show(&frame)? {
+            return Ok(());
+        }
+        kept.push(frame);
+    }
+
+    if kept.is_empty() {
+        return Ok(());
+    }
+    for _ in 1..loops {
+        for frame in &kept {
+            if show(frame)? {
+                return Ok(());
             }
         }
     }
 
+    is_stopped(deadline.saturating_duration_since(Instant::now()))?;
     Ok(())
 }
 
+/// Waits for the delay, returns whether Ctrl-C, q or Esc is pressed meanwhile
+fn is_stopped(delay: Duration) -> Result<bool, SymbolizeError> {
+    let deadline = Instant::now() + delay;
+    while event::poll(deadline.saturating_duration_since(Instant::now()))? {
+        match event::read()? {
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers,
+            }) if modifiers.contains(KeyModifiers::CONTROL) => return Ok(true),
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('q') | KeyCode::Esc,
+                ..
+            }) => return Ok(true),
+            _ => {}
+        }
+    }
+
+    Ok(false)
+}
+
+/// Exit codes follow sysexits.h: 64 for invalid arguments, 65 for invalid input data,
+/// 66 for missing input files, 70 for failed encoding and 74 for other I/O errors
+fn get_exit_code(error: &SymbolizeError) -> i32 {
+    match error {
+        SymbolizeError::EmptyPalette
+        | SymbolizeError::InvalidScale(_)
+        | SymbolizeError::InvalidCellAspectRatio(_)
+        | SymbolizeError::ZeroSizedOutput
+        | SymbolizeError::OversizedOutput
+        | SymbolizeError::UnsupportedMode(_)
+        | SymbolizeError::InvalidOption { .. }
+        | SymbolizeError::MissingOption(_) => 64,
+        SymbolizeError::InvalidFont(_)
+        | SymbolizeError::MissingGlyphs
+        | SymbolizeError::Decode(_) => 65,
+        SymbolizeError::Encode(_) => 70,
+        SymbolizeError::Io(e) if e.kind() == io::ErrorKind::NotFound => 66,
+        SymbolizeError::Io(_) => 74,
+    }
+}
+
 struct FilterTypeWrapper(FilterType);
 
 impl TryInto<FilterTypeWrapper> for String {
-    type Error = Error;
+    type Error = SymbolizeError;
 
     fn try_into(self) -> Result<FilterTypeWrapper, Self::Error> {
         match self.as_str() {
@@ -78,10 +764,672 @@ impl TryInto<FilterTypeWrapper> for String {
             "catmull_rom" => Ok(FilterTypeWrapper(FilterType::CatmullRom)),
             "gaussian" => Ok(FilterTypeWrapper(FilterType::Gaussian)),
             "lanczos3" => Ok(FilterTypeWrapper(FilterType::Lanczos3)),
-            _ => Err(Error::new(
-                ErrorKind::InvalidData,
-                "unknown filter type, aborting",
-            )),
+            _ => Err(SymbolizeError::InvalidOption {
+                name: "filter type",
+                value: self.to_string(),
+            }),
         }
     }
 }
+
+fn get_budget(args: &Args) -> Result<(Option<u32>, Option<u32>), SymbolizeError> {
+    let (mut columns, mut rows) = (args.width, args.height);
+    if args.fit {
+        if !io::stdout().is_terminal() {
+            return Err(SymbolizeError::Io(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "can't fit the result into the terminal, stdout is not a terminal",
+            )));
+        }
+
+        // one row is left for the prompt that follows the result
+        let (terminal_columns, terminal_rows) = terminal::size()?;
+        columns = columns.or(Some(terminal_columns as u32));
+        rows = rows.or(Some((terminal_rows as u32).saturating_sub(1)));
+    }
+
+    Ok((columns, rows))
+}
+
+/// Name of the --format, or of the format guessed from the extension of --output or --extension
+fn get_format_name(args: &Args) -> String {
+    if let Some(format) = &args.format {
+        return format.clone();
+    }
+
+    let extension = match &args.output {
+        Some(output) => Path::new(output).extension().and_then(OsStr::to_str),
+        None => args.extension.as_deref(),
+    }
+    .map(str::to_ascii_lowercase);
+    let format = match extension.as_deref() {
+        Some("html" | "htm") => "html",
+        Some("svg") => "svg",
+        Some("json") => "json",
+        Some("cast") => "asciicast",
+        Some(extension) if ImageFormat::from_extension(extension).is_some() => "png",
+        _ => "ansi",
+    };
+
+    format.to_string()
+}
+
+fn get_format(args: &Args) -> Result<Format, SymbolizeError> {
+    let format = get_format_name(args);
+    match format.as_str() {
+        "ansi" => Ok(Format::Ansi),
+        "html" => Ok(Format::Html(HtmlOptions {
+            full_page: !args.fragment,
+            font_family: args.font_family.clone(),
+            font_size: args.font_size,
+        })),
+        "svg" => {
+            let (cell_width, cell_height) = match &args.svg_cell_size {
+                Some(cell_size) => get_svg_cell_size(cell_size)?,
+                // a typical monospace glyph is 0.6 of the font size wide
+                None => {
+                    let cell_width = args.font_size * 0.6;
+                    (cell_width, cell_width * args.cell_aspect_ratio)
+                }
+            };
+
+            Ok(Format::Svg(SvgOptions {
+                font_family: args.font_family.clone(),
+                font_size: args.font_size,
+                cell_width,
+                cell_height,
+            }))
+        }
+        "png" => {
+            let (cell_width, cell_height) = get_cell_size(&args.cell_size)?;
+            Ok(Format::Png(RasterOptions {
+                font: args.font.as_ref().map(Font::open).transpose()?,
+                cell_width,
+                cell_height,
+                ..Default::default()
+            }))
+        }
+        #[cfg(feature = "serde")]
+        "json" => Ok(Format::Json),
+        "asciicast" => Ok(Format::Asciicast(AsciicastOptions {
+            title: args.title.clone(),
+            loops: args.loops.unwrap_or(1),
+        })),
+        _ => Err(SymbolizeError::InvalidOption {
+            name: "format",
+            value: format,
+        }),
+    }
+}
+
+fn get_svg_cell_size(cell_size: &str) -> Result<(f32, f32), SymbolizeError> {
+    let invalid_cell_size = || SymbolizeError::InvalidOption {
+        name: "svg cell size",
+        value: cell_size.to_string(),
+    };
+
+    let (cell_width, cell_height) = cell_size.split_once('x').ok_or_else(invalid_cell_size)?;
+    let (cell_width, cell_height): (f32, f32) = (
+        cell_width.parse().map_err(|_| invalid_cell_size())?,
+        cell_height.parse().map_err(|_| invalid_cell_size())?,
+    );
+    if cell_width <= 0.0 || cell_height <= 0.0 {
+        return Err(invalid_cell_size());
+    }
+
+    Ok((cell_width, cell_height))
+}
+
+fn get_color_depth(args: &Args, format: &Format) -> Result<ColorDepth, SymbolizeError> {
+    // only a terminal is limited in colors
+    let detect = || {
+        if !matches!(format, Format::Ansi) {
+            return ColorDepth::TrueColor;
+        }
+
+        get_terminal_color_depth(
+            args,
+            env::var("COLORTERM").ok().as_deref(),
+            env::var("TERM").ok().as_deref(),
+        )
+    };
+
+    match args.color_depth.as_deref() {
+        // the colors of every cell are the point of the json output
+        #[cfg(feature = "serde")]
+        None if matches!(format, Format::Json) => Ok(ColorDepth::TrueColor),
+        None if args.colorize || args.background != "none" => Ok(detect()),
+        None | Some("none") => Ok(ColorDepth::None),
+        Some("auto") => Ok(detect()),
+        Some("truecolor") => Ok(ColorDepth::TrueColor),
+        Some("256") => Ok(ColorDepth::Xterm256),
+        Some("16") => Ok(ColorDepth::Ansi16),
+        Some("8") => Ok(ColorDepth::Ansi8),
+        _ => Err(SymbolizeError::InvalidOption {
+            name: "color depth",
+            value: args.color_depth.as_deref().unwrap_or_default().to_string(),
+        }),
+    }
+}
+
+/// Color depth detected from COLORTERM and TERM. A background mode can't be drawn without colors,
+/// so it falls back to the 8 basic colors every color terminal has
+fn get_terminal_color_depth(
+    args: &Args,
+    colorterm: Option<&str>,
+    term: Option<&str>,
+) -> ColorDepth {
+    match ColorDepth::detect(colorterm, term) {
+        ColorDepth::None if args.background != "none" => ColorDepth::Ansi8,
+        color_depth => color_depth,
+    }
+}
+
+fn get_background(background: &str) -> Result<Background, SymbolizeError> {
+    match background {
+        "none" => Ok(Background::None),
+        "fill" => Ok(Background::Fill),
+        "contrast" => Ok(Background::Contrast),
+        "solid" => Ok(Background::Solid),
+        _ => Err(SymbolizeError::InvalidOption {
+            name: "background mode",
+            value: background.to_string(),
+        }),
+    }
+}
+
+fn get_alpha(args: &Args) -> Result<AlphaOptions, SymbolizeError> {
+    let matte = match &args.matte {
+        Some(matte) => Some(
+            get_color(matte).ok_or_else(|| SymbolizeError::InvalidOption {
+                name: "matte",
+                value: matte.clone(),
+            })?,
+        ),
+        None => None,
+    };
+
+    Ok(AlphaOptions {
+        threshold: args.alpha_threshold,
+        matte,
+    })
+}
+
+/// Parses a color in #rrggbb format, the # is optional
+fn get_color(color: &str) -> Option<Rgb<u8>> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |idx: usize| u8::from_str_radix(&hex[idx..idx + 2], 16).ok();
+    Some(Rgb([channel(0)?, channel(2)?, channel(4)?]))
+}
+
+fn get_mode(args: &Args) -> Result<Mode, SymbolizeError> {
+    match args.mode.as_str() {
+        "palette" => Ok(Mode::Palette(PaletteOptions {
+            quantizer: get_quantizer(&args.quantizer)?,
+            dither: get_dither(&args.dither)?,
+        })),
+        "ramp" => Ok(Mode::Ramp(RampOptions {
+            invert: args.invert,
+            curve: get_ramp_curve(&args.curve)?,
+        })),
+        "glyph" => Ok(Mode::Glyph(get_glyph_options(args)?)),
+        "braille" => Ok(Mode::Braille(BrailleOptions {
+            threshold: args.threshold,
+            invert: args.invert,
+            dither: get_dither(&args.dither)?,
+        })),
+        "half_block" => Ok(Mode::HalfBlock),
+        "quadrant" => Ok(Mode::Quadrant),
+        "sextant" => Ok(Mode::Sextant),
+        "octant" => Ok(Mode::Octant),
+        mode => Err(SymbolizeError::UnsupportedMode(mode.to_string())),
+    }
+}
+
+fn get_ramp_curve(curve: &str) -> Result<RampCurve, SymbolizeError> {
+    let invalid_curve = || SymbolizeError::InvalidOption {
+        name: "ramp curve",
+        value: curve.to_string(),
+    };
+
+    if curve == "linear" {
+        return Ok(RampCurve::Linear);
+    }
+
+    if let Some(gamma) = curve.strip_prefix("gamma:") {
+        return gamma
+            .parse()
+            .map(RampCurve::Gamma)
+            .map_err(|_| invalid_curve());
+    }
+
+    curve
+        .split(',')
+        .map(|level| level.trim().parse())
+        .collect::<Result<Vec<f32>, _>>()
+        .map(RampCurve::Custom)
+        .map_err(|_| invalid_curve())
+}
+
+fn get_glyph_options(args: &Args) -> Result<GlyphOptions, SymbolizeError> {
+    let font_path = args
+        .font
+        .as_ref()
+        .ok_or(SymbolizeError::MissingOption("font"))?;
+    let font = Font::open(font_path)?;
+    let (cell_width, cell_height) = get_cell_size(&args.cell_size)?;
+
+    let matching = match args.matching.as_str() {
+        "least_squares" => GlyphMatching::LeastSquares,
+        "ssim" => GlyphMatching::Ssim,
+        _ => {
+            return Err(SymbolizeError::InvalidOption {
+                name: "matching method",
+                value: args.matching.to_string(),
+            })
+        }
+    };
+
+    Ok(GlyphOptions {
+        font,
+        cell_width,
+        cell_height,
+        invert: args.invert,
+        matching,
+    })
+}
+
+fn get_cell_size(cell_size: &str) -> Result<(u32, u32), SymbolizeError> {
+    let invalid_cell_size = || SymbolizeError::InvalidOption {
+        name: "cell size",
+        value: cell_size.to_string(),
+    };
+
+    let (cell_width, cell_height) = cell_size.split_once('x').ok_or_else(invalid_cell_size)?;
+    let (cell_width, cell_height) = (
+        cell_width.parse().map_err(|_| invalid_cell_size())?,
+        cell_height.parse().map_err(|_| invalid_cell_size())?,
+    );
+    if cell_width == 0 || cell_height == 0 {
+        return Err(invalid_cell_size());
+    }
+
+    Ok((cell_width, cell_height))
+}
+
+fn get_metric(metric: &str) -> Result<DistanceMetric, SymbolizeError> {
+    match metric {
+        "manhattan" => Ok(DistanceMetric::Manhattan),
+        "euclidean" => Ok(DistanceMetric::Euclidean),
+        "redmean" => Ok(DistanceMetric::Redmean),
+        "cie76" => Ok(DistanceMetric::Cie76),
+        "ciede2000" => Ok(DistanceMetric::Ciede2000),
+        _ => Err(SymbolizeError::InvalidOption {
+            name: "distance metric",
+            value: metric.to_string(),
+        }),
+    }
+}
+
+fn get_dither(dither: &str) -> Result<Dither, SymbolizeError> {
+    match dither {
+        "none" => Ok(Dither::None),
+        "floyd_steinberg" => Ok(Dither::FloydSteinberg),
+        "atkinson" => Ok(Dither::Atkinson),
+        "jarvis_judice_ninke" => Ok(Dither::JarvisJudiceNinke),
+        "sierra" => Ok(Dither::Sierra),
+        "bayer2" => Ok(Dither::Bayer2),
+        "bayer4" => Ok(Dither::Bayer4),
+        "bayer8" => Ok(Dither::Bayer8),
+        _ => Err(SymbolizeError::InvalidOption {
+            name: "dithering algorithm",
+            value: dither.to_string(),
+        }),
+    }
+}
+
+fn get_quantizer(quantizer: &str) -> Result<Quantizer, SymbolizeError> {
+    match quantizer {
+        "most_used" => Ok(Quantizer::MostUsed),
+        "median_cut" => Ok(Quantizer::MedianCut),
+        "k_means" => Ok(Quantizer::KMeans),
+        "octree" => Ok(Quantizer::Octree),
+        _ => Err(SymbolizeError::InvalidOption {
+            name: "quantizer",
+            value: quantizer.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env, fs, io,
+        path::{Path, PathBuf},
+        process,
+    };
+
+    use clap::Parser;
+    use std::time::Duration;
+
+    use image::{open, Delay, Frame, Rgb, RgbaImage};
+    use symbolize::{Cell, ColorDepth, SymbolizeError};
+
+    use super::{
+        drop_frames, fit_to_terminal, get_color_depth, get_exit_code, get_format, get_format_name,
+        get_glob_base, get_inputs, get_symbolizer, get_terminal_color_depth, open_image,
+        open_image_frames, run_batch, Args, Format,
+    };
+
+    fn get_args(args: &[&str]) -> Args {
+        Args::try_parse_from([&["symbolize", "ferris.png"], args].concat()).unwrap()
+    }
+
+    /// Empty directory of the test with a.png, c.jpg, notes.txt and sub/b.png created in it
+    fn get_pictures_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("symbolize-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        for picture in ["a.png", "c.jpg", "sub/b.png"] {
+            fs::copy("./test-data/ferris.png", dir.join(picture)).unwrap();
+        }
+        fs::write(dir.join("notes.txt"), "not a picture").unwrap();
+
+        dir
+    }
+
+    fn get_outputs(paths: &[PathBuf], extension: &str) -> Vec<(PathBuf, PathBuf)> {
+        let paths: Vec<String> = paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        let (inputs, failures) = get_inputs(&paths, extension).unwrap();
+        assert!(failures.is_empty());
+
+        inputs
+    }
+
+    #[test]
+    fn finds_fixed_part_of_glob_pattern() {
+        assert_eq!(get_glob_base("pictures/**/*.png"), Path::new("pictures"));
+        assert_eq!(get_glob_base("/tmp/a/b?.png"), Path::new("/tmp/a"));
+        assert_eq!(get_glob_base("[ab].png"), Path::new(""));
+    }
+
+    #[test]
+    fn mirrors_pictures_tree_in_output_dir() {
+        let dir = get_pictures_dir("tree");
+
+        assert_eq!(
+            get_outputs(&[dir.join("sub"), dir.join("a.png")], "txt"),
+            vec![
+                (dir.join("sub/b.png"), PathBuf::from("b.txt")),
+                (dir.join("a.png"), PathBuf::from("a.txt")),
+            ]
+        );
+        assert_eq!(
+            get_outputs(&[dir.join("*.png"), dir.join("**/b.png")], "svg"),
+            vec![
+                (dir.join("a.png"), PathBuf::from("a.svg")),
+                (dir.join("sub/b.png"), PathBuf::from("sub/b.svg")),
+            ]
+        );
+        // a.png is matched by both paths but converted once
+        assert_eq!(
+            get_outputs(&[dir.join("a.png"), dir.join("*.png")], "txt"),
+            vec![(dir.join("a.png"), PathBuf::from("a.txt"))]
+        );
+    }
+
+    #[test]
+    fn returns_error_if_pictures_share_output_path() {
+        let dir = get_pictures_dir("collision");
+        fs::create_dir(dir.join("other")).unwrap();
+        for picture in ["sub/a.png", "c.png", "other/b.png"] {
+            fs::copy("./test-data/ferris.png", dir.join(picture)).unwrap();
+        }
+
+        for paths in [
+            // pictures of the same name in different directories
+            [dir.join("a.png"), dir.join("sub/a.png")],
+            // pictures of a directory that differ only in the extension
+            [dir.join("c.*"), dir.join("sub/b.png")],
+            // directories with the same layout
+            [dir.join("sub"), dir.join("other")],
+        ] {
+            let paths: Vec<String> = paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect();
+
+            assert!(
+                matches!(
+                    get_inputs(&paths, "txt"),
+                    Err(SymbolizeError::InvalidOption {
+                        name: "output path",
+                        ..
+                    })
+                ),
+                "{:?}",
+                paths
+            );
+        }
+    }
+
+    #[test]
+    fn counts_failed_pictures_of_batch() {
+        let dir = get_pictures_dir("batch");
+        fs::write(dir.join("sub/broken.png"), "not a picture").unwrap();
+        let output_dir = dir.join("output");
+        let args = Args::try_parse_from([
+            "symbolize",
+            &dir.join("**/*.png").display().to_string(),
+            "--scale",
+            "0.01",
+            "--output-dir",
+            &output_dir.display().to_string(),
+        ])
+        .unwrap();
+
+        let failures = run_batch(&args, &output_dir).unwrap();
+
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, dir.join("sub/broken.png"));
+        assert_eq!(get_exit_code(&failures[0].1), 65);
+        assert!(output_dir.join("a.txt").is_file());
+        assert!(output_dir.join("sub/b.txt").is_file());
+        assert!(!output_dir.join("sub/broken.txt").exists());
+    }
+
+    #[test]
+    fn guesses_format_from_output_extension() {
+        for (output, format) in [
+            ("ferris.html", "html"),
+            ("ferris.HTM", "html"),
+            ("ferris.svg", "svg"),
+            ("ferris.json", "json"),
+            ("ferris.cast", "asciicast"),
+            ("ferris.png", "png"),
+            ("ferris.jpg", "png"),
+            ("ferris.webp", "png"),
+            ("ferris.txt", "ansi"),
+            ("ferris.unknown", "ansi"),
+            ("ferris", "ansi"),
+        ] {
+            assert_eq!(
+                get_format_name(&get_args(&["-o", output])),
+                format,
+                "{}",
+                output
+            );
+        }
+
+        assert_eq!(get_format_name(&get_args(&["--extension", "svg"])), "svg");
+        assert!(matches!(
+            get_format(&get_args(&["-o", "ferris.jpg"])),
+            Ok(Format::Png(_))
+        ));
+    }
+
+    #[test]
+    fn prefers_format_option_to_output_extension() {
+        let args = get_args(&["-o", "ferris.html", "--format", "svg"]);
+        assert_eq!(get_format_name(&args), "svg");
+        assert!(matches!(get_format(&args), Ok(Format::Svg(_))));
+
+        // the image is still written to the path whatever its extension is
+        let args = get_args(&["-o", "ferris.png", "--format", "ansi"]);
+        assert!(matches!(get_format(&args), Ok(Format::Ansi)));
+
+        let args = get_args(&["-o", "ferris.html", "--format", "bmp"]);
+        assert!(matches!(
+            get_format(&args),
+            Err(SymbolizeError::InvalidOption { name: "format", value }) if value == "bmp"
+        ));
+    }
+
+    #[test]
+    fn reads_image_from_stdin_for_dash() {
+        let png = fs::read("./test-data/ferris.png").unwrap();
+        let from_stdin = open_image(Path::new("-"), &png[..]).unwrap();
+        let from_file = open_image(Path::new("./test-data/ferris.png"), io::empty()).unwrap();
+        assert_eq!(from_stdin, from_file);
+
+        let frames = open_image_frames(Path::new("-"), &png[..]).unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].buffer(), &from_file.into_rgba8());
+    }
+
+    #[test]
+    fn returns_decode_error_for_unknown_stdin_data() {
+        let result = open_image(Path::new("-"), &b"not an image"[..]);
+
+        assert!(matches!(result, Err(SymbolizeError::Decode(_))));
+        assert_eq!(get_exit_code(&result.unwrap_err()), 65);
+        assert!(matches!(
+            open_image(Path::new("-"), io::empty()),
+            Err(SymbolizeError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn colorizes_json_by_default() {
+        let color_depth = |args: &[&str]| {
+            let args = get_args(args);
+            get_color_depth(&args, &get_format(&args).unwrap()).unwrap()
+        };
+
+        #[cfg(feature = "serde")]
+        {
+            assert_eq!(color_depth(&["--format", "json"]), ColorDepth::TrueColor);
+            assert_eq!(
+                color_depth(&["--format", "json", "--color-depth", "none"]),
+                ColorDepth::None
+            );
+        }
+        assert_eq!(color_depth(&["--format", "html"]), ColorDepth::None);
+        assert_eq!(
+            color_depth(&["--format", "html", "-c"]),
+            ColorDepth::TrueColor
+        );
+    }
+
+    #[test]
+    fn draws_background_with_basic_colors_on_terminal_without_colors() {
+        let solid = get_args(&["--background", "solid"]);
+        assert_eq!(
+            get_terminal_color_depth(&solid, None, Some("dumb")),
+            ColorDepth::Ansi8
+        );
+        assert_eq!(
+            get_terminal_color_depth(&solid, Some("truecolor"), None),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            get_terminal_color_depth(&get_args(&["-c"]), None, Some("dumb")),
+            ColorDepth::None
+        );
+    }
+
+    #[test]
+    fn leaves_transparent_border_of_ferris_blank() {
+        let symbolize_ferris = |args: &[&str]| {
+            let args = get_args(&[&["--scale", "0.01", "-m", "octant", "-c"], args].concat());
+            get_symbolizer(&args, &get_format(&args).unwrap())
+                .unwrap()
+                .symbolize(open("./test-data/ferris.png").unwrap())
+                .unwrap()
+        };
+        let is_blank = |cell: &Cell| cell.symbol == ' ' && cell.fg.is_none() && cell.bg.is_none();
+
+        let result = symbolize_ferris(&[]);
+        assert!(result.cells[0].iter().all(is_blank));
+        assert!(result.cells.iter().all(|row| is_blank(&row[0])));
+        assert!(!result.cells.iter().flatten().all(is_blank));
+
+        // without a threshold the transparent pixels are drawn in the black they store
+        let result = symbolize_ferris(&["--alpha-threshold", "0"]);
+        assert_eq!(result.cells[0][0].fg, Some(Rgb([0, 0, 0])));
+    }
+
+    #[test]
+    fn drops_frames_above_max_fps() {
+        let get_frames = |delays_ms: &[u32]| {
+            delays_ms
+                .iter()
+                .map(|&ms| {
+                    Frame::from_parts(
+                        RgbaImage::new(1, 1),
+                        0,
+                        0,
+                        Delay::from_numer_denom_ms(ms, 1),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let get_delays = |frames: Vec<Frame>| {
+            frames
+                .into_iter()
+                .map(|frame| Duration::from(frame.delay()).as_millis())
+                .collect::<Vec<_>>()
+        };
+
+        let frames = drop_frames(get_frames(&[10, 10, 10, 10, 50, 20]), 25.0).unwrap();
+        assert_eq!(get_delays(frames), [40, 50, 20]);
+
+        let frames = drop_frames(get_frames(&[10, 10, 10]), 0.0).unwrap();
+        assert_eq!(get_delays(frames), [10, 10, 10]);
+
+        for max_fps in [-1.0, f32::NAN, f32::MIN_POSITIVE / 4.0] {
+            assert!(matches!(
+                drop_frames(get_frames(&[10]), max_fps),
+                Err(SymbolizeError::InvalidOption {
+                    name: "max fps",
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn fits_played_frames_into_terminal() {
+        let get_budget = |args: &[&str]| {
+            let args = get_args(args);
+            let symbolizer = get_symbolizer(&args, &get_format(&args).unwrap()).unwrap();
+            let options = fit_to_terminal(symbolizer, (400, 200), (80, 25))
+                .options()
+                .clone();
+            (options.columns, options.rows)
+        };
+
+        assert_eq!(get_budget(&[]), (Some(80), Some(24)));
+        assert_eq!(get_budget(&["--width", "120"]), (Some(80), Some(24)));
+        assert_eq!(get_budget(&["--height", "10"]), (Some(80), Some(10)));
+        // a scale that already fits is kept
+        assert_eq!(get_budget(&["--scale", "0.05"]), (None, None));
+    }
+}
@@ -0,0 +1,378 @@
+use std::collections::HashMap;
+
+use image::Rgb;
+
+/// Defines how the palette colors, which the symbols are assigned to, are chosen from the image
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Quantizer {
+    /// The most frequent exact colors of the image. Works best for images with few flat colors
+    #[default]
+    MostUsed,
+    /// Colors are split into boxes by the median of the widest channel until there are enough boxes
+    MedianCut,
+    /// Colors are clustered with k-means seeded by the most used color and the farthest colors from it
+    KMeans,
+    /// Colors are put into an octree whose least populated leaves are merged until there are few enough
+    Octree,
+}
+
+/// Finds up to `count` representative colors of the pixels.
+/// Returns the colors with the amount of pixels they represent, the most represented colors go first.
+pub(crate) fn quantize<'a>(
+    pixels: impl Iterator<Item = &'a Rgb<u8>>,
+    count: usize,
+    quantizer: Quantizer,
+) -> Vec<(Rgb<u8>, usize)> {
+    let histogram = get_histogram(pixels);
+    if count == 0 || histogram.is_empty() {
+        return vec![];
+    }
+
+    let mut colors = match quantizer {
+        Quantizer::MostUsed => {
+            let mut colors = histogram;
+            colors.sort_by(|(first, first_count), (second, second_count)| {
+                second_count.cmp(first_count).then(first.0.cmp(&second.0))
+            });
+            colors.truncate(count);
+            colors
+        }
+        Quantizer::MedianCut => median_cut(histogram, count),
+        Quantizer::KMeans => k_means(histogram, count),
+        Quantizer::Octree => octree(histogram, count),
+    };
+
+    colors.sort_by(|(first, first_count), (second, second_count)| {
+        second_count.cmp(first_count).then(first.0.cmp(&second.0))
+    });
+    colors
+}
+
+/// Unique colors of the pixels with their amounts, sorted by color to keep the quantizers deterministic
+fn get_histogram<'a>(pixels: impl Iterator<Item = &'a Rgb<u8>>) -> Vec<(Rgb<u8>, usize)> {
+    let mut histogram: HashMap<Rgb<u8>, usize> = HashMap::new();
+    for pixel in pixels {
+        *histogram.entry(*pixel).or_default() += 1;
+    }
+
+    let mut histogram: Vec<(Rgb<u8>, usize)> = histogram.into_iter().collect();
+    histogram.sort_by_key(|(color, _)| color.0);
+    histogram
+}
+
+fn get_average(colors: &[(Rgb<u8>, usize)]) -> (Rgb<u8>, usize) {
+    let (mut sum, mut total) = ([0usize; 3], 0);
+    for (color, count) in colors {
+        for (channel_sum, channel) in sum.iter_mut().zip(color.0) {
+            *channel_sum += channel as usize * count;
+        }
+        total += count;
+    }
+
+    (
+        Rgb(sum.map(|channel_sum| ((channel_sum + total / 2) / total) as u8)),
+        total,
+    )
+}
+
+fn median_cut(histogram: Vec<(Rgb<u8>, usize)>, count: usize) -> Vec<(Rgb<u8>, usize)> {
+    let get_widest_channel = |colors: &[(Rgb<u8>, usize)]| {
+        (0..3)
+            .map(|channel| {
+                let (min, max) = colors
+                    .iter()
+                    .fold((u8::MAX, u8::MIN), |(min, max), (color, _)| {
+                        (min.min(color.0[channel]), max.max(color.0[channel]))
+                    });
+                (channel, max - min)
+            })
+            .max_by_key(|(channel, range)| (*range, 2 - channel))
+            .unwrap_or((0, 0))
+    };
+
+    let mut boxes = vec![histogram];
+    while boxes.len() < count {
+        // the box with the widest range is split, boxes of a single color can't be split at all
+        let Some((idx, (channel, _))) = boxes
+            .iter()
+            .map(|colors| get_widest_channel(colors))
+            .enumerate()
+            .filter(|(_, (_, range))| *range > 0)
+            .max_by_key(|(idx, (_, range))| (*range, usize::MAX - idx))
+        else {
+            break;
+        };
+
+        let mut colors = boxes.swap_remove(idx);
+        colors.sort_by_key(|(color, _)| (color.0[channel], color.0));
+        let half = colors.iter().map(|(_, count)| count).sum::<usize>() / 2;
+        let mut accumulated = 0;
+        let mut split_at = colors
+            .iter()
+            .position(|(_, count)| {
+                accumulated += count;
+                accumulated > half
+            })
+            .unwrap_or(colors.len() - 1);
+        split_at = split_at.clamp(1, colors.len() - 1);
+
+        let rest = colors.split_off(split_at);
+        boxes.push(colors);
+        boxes.push(rest);
+    }
+
+    boxes.iter().map(|colors| get_average(colors)).collect()
+}
+
+fn k_means(histogram: Vec<(Rgb<u8>, usize)>, count: usize) -> Vec<(Rgb<u8>, usize)> {
+    const MAX_ITERATIONS: usize = 32;
+
+    let to_point = |color: &Rgb<u8>| color.0.map(|channel| channel as f32);
+    let get_distance = |first: &[f32; 3], second: &[f32; 3]| {
+        (0..3).map(|i| (first[i] - second[i]).powi(2)).sum::<f32>()
+    };
+
+    // the most used color and then the colors farthest from the already chosen centers
+    let mut centers = vec![];
+    if let Some((color, _)) = histogram
+        .iter()
+        .max_by_key(|(color, count)| (*count, color.0))
+    {
+        centers.push(to_point(color));
+    }
+    while centers.len() < count.min(histogram.len()) {
+        let farthest = histogram
+            .iter()
+            .map(|(color, _)| {
+                let point = to_point(color);
+                let distance = centers
+                    .iter()
+                    .map(|center| get_distance(&point, center))
+                    .fold(f32::MAX, f32::min);
+                (point, distance)
+            })
+            .max_by(|(_, first), (_, second)| first.total_cmp(second));
+
+        match farthest {
+            Some((point, distance)) if distance > 0.0 => centers.push(point),
+            _ => break,
+        }
+    }
+
+    let mut assignments = vec![0; histogram.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let mut changed = false;
+        for ((color, _), assignment) in histogram.iter().zip(assignments.iter_mut()) {
+            let point = to_point(color);
+            let nearest = (0..centers.len())
+                .min_by(|first, second| {
+                    get_distance(&point, &centers[*first])
+                        .total_cmp(&get_distance(&point, &centers[*second]))
+                })
+                .unwrap_or(0);
+            if nearest != *assignment {
+                *assignment = nearest;
+                changed = true;
+            }
+        }
+
+        let mut sums = vec![([0.0f32; 3], 0usize); centers.len()];
+        for ((color, count), assignment) in histogram.iter().zip(&assignments) {
+            let (sum, total) = &mut sums[*assignment];
+            for (channel_sum, channel) in sum.iter_mut().zip(color.0) {
+                *channel_sum += channel as f32 * *count as f32;
+            }
+            *total += count;
+        }
+        for (center, (sum, total)) in centers.iter_mut().zip(&sums) {
+            if *total > 0 {
+                *center = sum.map(|channel_sum| channel_sum / *total as f32);
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    (0..centers.len())
+        .filter_map(|cluster| {
+            let colors: Vec<(Rgb<u8>, usize)> = histogram
+                .iter()
+                .zip(&assignments)
+                .filter(|(_, assignment)| **assignment == cluster)
+                .map(|(color, _)| *color)
+                .collect();
+
+            (!colors.is_empty()).then(|| get_average(&colors))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+struct OctreeLeaf {
+    depth: u32,
+    key: u32,
+    colors: Vec<(Rgb<u8>, usize)>,
+}
+
+impl OctreeLeaf {
+    fn count(&self) -> usize {
+        self.colors.iter().map(|(_, count)| count).sum()
+    }
+}
+
+fn octree(histogram: Vec<(Rgb<u8>, usize)>, count: usize) -> Vec<(Rgb<u8>, usize)> {
+    const MAX_DEPTH: u32 = 8;
+
+    // every level of the tree takes the next most significant bit of all three channels
+    let get_key = |color: &Rgb<u8>| {
+        (0..MAX_DEPTH).fold(0u32, |key, level| {
+            let bit = 7 - level;
+            let [r, g, b] = color.0.map(|channel| (channel >> bit & 1) as u32);
+            key << 3 | r << 2 | g << 1 | b
+        })
+    };
+
+    let mut leaves: Vec<OctreeLeaf> = histogram
+        .into_iter()
+        .map(|(color, count)| OctreeLeaf {
+            depth: MAX_DEPTH,
+            key: get_key(&color),
+            colors: vec![(color, count)],
+        })
+        .collect();
+
+    while leaves.len() > count {
+        let depth = leaves.iter().map(|leaf| leaf.depth).max().unwrap_or(0);
+        if depth == 0 {
+            break;
+        }
+
+        // the deepest leaves are merged into their parents, the least populated parents go first
+        let mut parents: HashMap<u32, Vec<OctreeLeaf>> = HashMap::new();
+        let mut rest = vec![];
+        for leaf in leaves {
+            if leaf.depth == depth {
+                parents.entry(leaf.key >> 3).or_default().push(leaf);
+            } else {
+                rest.push(leaf);
+            }
+        }
+
+        let mut parents: Vec<(u32, Vec<OctreeLeaf>)> = parents.into_iter().collect();
+        parents.sort_by_key(|(key, children)| {
+            (children.iter().map(OctreeLeaf::count).sum::<usize>(), *key)
+        });
+
+        let mut leaves_left = rest.len()
+            + parents
+                .iter()
+                .map(|(_, children)| children.len())
+                .sum::<usize>();
+        for (key, children) in parents {
+            if leaves_left <= count {
+                rest.extend(children);
+                continue;
+            }
+
+            leaves_left -= children.len() - 1;
+            rest.push(OctreeLeaf {
+                depth: depth - 1,
+                key,
+                colors: children
+                    .into_iter()
+                    .flat_map(|child| child.colors)
+                    .collect(),
+            });
+        }
+        leaves = rest;
+    }
+
+    leaves
+        .iter()
+        .map(|leaf| get_average(&leaf.colors))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+
+    use super::{quantize, Quantizer};
+
+    /// Two equally sized noisy clusters: a red one and a blue one
+    fn get_noisy_image() -> RgbImage {
+        RgbImage::from_fn(16, 16, |x, y| {
+            let noise = ((x * 7 + y * 13) % 11) as u8;
+            if y < 8 {
+                Rgb([200 + noise, 20 + noise, 20])
+            } else {
+                Rgb([20, 20 + noise, 200 + noise])
+            }
+        })
+    }
+
+    fn is_near(color: &Rgb<u8>, expected: [u8; 3]) -> bool {
+        color
+            .0
+            .iter()
+            .zip(expected)
+            .all(|(channel, expected)| channel.abs_diff(expected) <= 10)
+    }
+
+    #[test]
+    fn finds_representative_colors_of_clusters() {
+        for quantizer in [Quantizer::MedianCut, Quantizer::KMeans, Quantizer::Octree] {
+            let colors = quantize(get_noisy_image().pixels(), 2, quantizer);
+
+            assert_eq!(colors.len(), 2, "{:?}", quantizer);
+            assert!(
+                colors
+                    .iter()
+                    .any(|(color, _)| is_near(color, [205, 25, 20])),
+                "{:?}",
+                quantizer
+            );
+            assert!(
+                colors
+                    .iter()
+                    .any(|(color, _)| is_near(color, [20, 25, 205])),
+                "{:?}",
+                quantizer
+            );
+            assert_eq!(colors[0].1 + colors[1].1, 256, "{:?}", quantizer);
+        }
+    }
+
+    #[test]
+    fn picks_most_used_exact_colors() {
+        let image = RgbImage::from_fn(6, 1, |x, _| match x {
+            0..=2 => Rgb([1, 1, 1]),
+            3..=4 => Rgb([2, 2, 2]),
+            _ => Rgb([3, 3, 3]),
+        });
+
+        assert_eq!(
+            quantize(image.pixels(), 2, Quantizer::MostUsed),
+            vec![(Rgb([1, 1, 1]), 3), (Rgb([2, 2, 2]), 2)]
+        );
+    }
+
+    #[test]
+    fn returns_no_more_colors_than_image_has() {
+        let image = RgbImage::from_pixel(4, 4, Rgb([1, 2, 3]));
+
+        for quantizer in [
+            Quantizer::MostUsed,
+            Quantizer::MedianCut,
+            Quantizer::KMeans,
+            Quantizer::Octree,
+        ] {
+            assert_eq!(
+                quantize(image.pixels(), 3, quantizer),
+                vec![(Rgb([1, 2, 3]), 16)]
+            );
+        }
+    }
+}
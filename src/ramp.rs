@@ -0,0 +1,140 @@
+use image::{Rgb, RgbImage};
+
+use crate::{color::to_linear, Cell};
+
+/// Options of the [`crate::Mode::Ramp`] rendering mode
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RampOptions {
+    /// Treat the palette as ordered from light to dark, e.g. for dark text on a light background
+    pub invert: bool,
+    /// Curve applied to the lightness of a pixel before picking a symbol
+    pub curve: RampCurve,
+}
+
+/// Maps the perceived lightness of a pixel (0.0-1.0) to a position on the palette ramp (0.0-1.0)
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum RampCurve {
+    /// Lightness is used as is
+    #[default]
+    Linear,
+    /// Lightness is raised to the power, values > 1.0 give more room to the light symbols
+    Gamma(f32),
+    /// Lightness is interpolated between the levels spread evenly from 0.0 to 1.0,
+    /// e.g. `[0.0, 0.8, 1.0]` pushes the dark half of the image to the lighter symbols
+    Custom(Vec<f32>),
+}
+
+impl RampCurve {
+    fn apply(&self, lightness: f32) -> f32 {
+        let value = match self {
+            RampCurve::Linear => lightness,
+            RampCurve::Gamma(gamma) => lightness.powf(1.0 / gamma),
+            RampCurve::Custom(levels) => match levels.len() {
+                0 => lightness,
+                1 => levels[0],
+                _ => {
+                    let position = lightness * (levels.len() - 1) as f32;
+                    let idx = (position as usize).min(levels.len() - 2);
+                    let fraction = position - idx as f32;
+                    levels[idx] + (levels[idx + 1] - levels[idx]) * fraction
+                }
+            },
+        };
+
+        value.clamp(0.0, 1.0)
+    }
+}
+
+/// Picks a palette symbol for every pixel by its perceived lightness,
+/// the first symbol of the palette being the darkest one
+pub(crate) fn symbolize(
+    image: &RgbImage,
+    palette: &[char],
+    options: &RampOptions,
+) -> Vec<Vec<Cell>> {
+    image
+        .rows()
+        .map(|row| {
+            row.map(|pixel| {
+                let mut position = options.curve.apply(get_perceived_lightness(pixel));
+                if options.invert {
+                    position = 1.0 - position;
+                }
+
+                let idx = ((position * palette.len() as f32) as usize).min(palette.len() - 1);
+                Cell::new(palette[idx], *pixel, None)
+            })
+            .collect()
+        })
+        .collect()
+}
+
+/// CIE L* lightness of the sRGB pixel scaled to 0.0-1.0
+pub(crate) fn get_perceived_lightness(pixel: &Rgb<u8>) -> f32 {
+    let [r, g, b] = pixel.0.map(to_linear);
+    let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+
+    let lightness = if luminance > 216.0 / 24389.0 {
+        116.0 * luminance.cbrt() - 16.0
+    } else {
+        luminance * 24389.0 / 27.0
+    };
+
+    lightness / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+
+    use super::{get_perceived_lightness, symbolize, RampCurve, RampOptions};
+
+    fn get_gradient() -> RgbImage {
+        RgbImage::from_fn(5, 1, |x, _| {
+            let value = (x * 255 / 4) as u8;
+            Rgb([value, value, value])
+        })
+    }
+
+    fn get_symbols(image: &RgbImage, options: &RampOptions) -> String {
+        symbolize(image, &[' ', '.', '+', '#'], options)[0]
+            .iter()
+            .map(|cell| cell.symbol)
+            .collect()
+    }
+
+    #[test]
+    fn computes_perceived_lightness() {
+        assert_eq!(get_perceived_lightness(&Rgb([0, 0, 0])), 0.0);
+        assert!((get_perceived_lightness(&Rgb([255, 255, 255])) - 1.0).abs() < 1e-4);
+        assert!((get_perceived_lightness(&Rgb([119, 119, 119])) - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn maps_gradient_from_dark_to_light() {
+        assert_eq!(
+            get_symbols(&get_gradient(), &RampOptions::default()),
+            " .+##"
+        );
+    }
+
+    #[test]
+    fn inverts_ramp() {
+        let options = RampOptions {
+            invert: true,
+            ..Default::default()
+        };
+
+        assert_eq!(get_symbols(&get_gradient(), &options), "#+.  ");
+    }
+
+    #[test]
+    fn applies_custom_curve() {
+        let options = RampOptions {
+            curve: RampCurve::Custom(vec![0.0, 0.0, 1.0]),
+            ..Default::default()
+        };
+
+        assert_eq!(get_symbols(&get_gradient(), &options), "   +#");
+    }
+}
@@ -0,0 +1,268 @@
+use std::{collections::HashMap, path::Path};
+
+use image::{DynamicImage, ImageFormat, Rgb, Rgba, RgbaImage};
+
+use crate::{
+    blocks::{self, get_block_shape},
+    braille, Cell, ColorDepth, Font, SymbolizeError, SymbolizeResult,
+};
+
+/// Options of the raster output, see [`SymbolizeResult::to_image`]
+#[derive(Debug, Clone)]
+pub struct RasterOptions {
+    /// Font the symbols are drawn with, the built-in one ([`Font::builtin`]) is used for the symbols it lacks
+    pub font: Option<Font>,
+    /// Width of a cell in pixels
+    pub cell_width: u32,
+    /// Height of a cell in pixels
+    pub cell_height: u32,
+    /// Color of the symbols without a color of their own
+    pub foreground: Rgba<u8>,
+    /// Color of the cells without a background color of their own
+    pub background: Rgba<u8>,
+}
+
+impl Default for RasterOptions {
+    fn default() -> Self {
+        Self {
+            font: None,
+            cell_width: 8,
+            cell_height: 16,
+            foreground: Rgba([229, 229, 229, 255]),
+            background: Rgba([0, 0, 0, 255]),
+        }
+    }
+}
+
+/// Draws the grid into an image, every cell takes `cell_width` x `cell_height` pixels.
+///
+/// Block elements and braille patterns are drawn as shapes, so they join seamlessly whatever the font is.
+pub(crate) fn render(
+    result: &SymbolizeResult,
+    options: &RasterOptions,
+) -> Result<RgbaImage, SymbolizeError> {
+    let colorize = result.color_depth != ColorDepth::None;
+    let (cell_width, cell_height) = (options.cell_width, options.cell_height);
+    let columns = result.cells.iter().map(Vec::len).max().unwrap_or(0);
+    let rows = result.cells.len();
+
+    // pixels are indexed with u32 below, the whole image has to fit in it and so does every cell
+    let get_size = |count: usize, cell_size: u32| u32::try_from(count).ok()?.checked_mul(cell_size);
+    let (width, height) = match (get_size(columns, cell_width), get_size(rows, cell_height)) {
+        (Some(width), Some(height)) if width.checked_mul(height).is_some() => (width, height),
+        _ => return Err(SymbolizeError::OversizedOutput),
+    };
+    let mut image = RgbaImage::from_pixel(width, height, options.background);
+
+    let mut coverages: HashMap<char, Vec<f32>> = HashMap::new();
+    for (row_idx, row) in result.cells.iter().enumerate() {
+        for (column, cell) in row.iter().enumerate() {
+            let coverage = coverages.entry(cell.symbol).or_insert_with(|| {
+                get_shape_coverage(cell.symbol, cell_width, cell_height)
+                    .or_else(|| {
+                        options
+                            .font
+                            .as_ref()
+                            .and_then(|font| font.rasterize(cell.symbol, cell_width, cell_height))
+                    })
+                    .or_else(|| Font::builtin().rasterize(cell.symbol, cell_width, cell_height))
+                    .unwrap_or_else(|| vec![0.0; (cell_width * cell_height) as usize])
+            });
+
+            let (fg, bg) = get_colors(cell, colorize, options);
+            for y in 0..cell_height {
+                for x in 0..cell_width {
+                    let alpha = coverage[(y * cell_width + x) as usize];
+                    image.put_pixel(
+                        column as u32 * cell_width + x,
+                        row_idx as u32 * cell_height + y,
+                        Rgba([0, 1, 2, 3].map(|channel| {
+                            let (fg, bg) = (fg.0[channel] as f32, bg.0[channel] as f32);
+                            (bg + (fg - bg) * alpha).round() as u8
+                        })),
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(image)
+}
+
+/// Saves the drawn grid, the format is chosen by the extension of the path.
+/// Formats without an alpha channel get the image without it.
+pub(crate) fn save(image: RgbaImage, path: &Path) -> Result<(), SymbolizeError> {
+    let format = ImageFormat::from_path(path).map_err(SymbolizeError::Encode)?;
+    let image = DynamicImage::ImageRgba8(image);
+    let image = match format {
+        ImageFormat::Jpeg | ImageFormat::Pnm | ImageFormat::Hdr => {
+            DynamicImage::ImageRgb8(image.to_rgb8())
+        }
+        _ => image,
+    };
+
+    image.save_with_format(path, format).map_err(|e| match e {
+        image::ImageError::IoError(e) => SymbolizeError::Io(e),
+        e => SymbolizeError::Encode(e),
+    })
+}
+
+fn get_colors(cell: &Cell, colorize: bool, options: &RasterOptions) -> (Rgba<u8>, Rgba<u8>) {
+    let opaque = |color: Rgb<u8>| Rgba([color.0[0], color.0[1], color.0[2], 255]);
+    if !colorize {
+        return (options.foreground, options.background);
+    }
+
+    (
+        cell.fg.map_or(options.foreground, opaque),
+        cell.bg.map_or(options.background, opaque),
+    )
+}
+
+/// Coverage of the cell drawn as a shape for block elements and braille patterns
+fn get_shape_coverage(symbol: char, width: u32, height: u32) -> Option<Vec<f32>> {
+    let mut coverage = vec![0.0; (width * height) as usize];
+    // dots are at least a pixel wide, so they are clipped to the cells smaller than the dot grid
+    let mut fill = |left: u32, top: u32, right: u32, bottom: u32| {
+        for y in top..bottom.min(height) {
+            for x in left..right.min(width) {
+                coverage[(y * width + x) as usize] = 1.0;
+            }
+        }
+    };
+
+    if let Some((layout, pattern)) = get_block_shape(symbol) {
+        let rows = layout.height();
+        for sub_row in 0..rows {
+            for sub_column in 0..blocks::BLOCK_WIDTH {
+                if pattern & 1 << (sub_row * blocks::BLOCK_WIDTH + sub_column) != 0 {
+                    // edges are rounded to whole pixels, so the neighbouring sub-pixels leave no gaps
+                    fill(
+                        sub_column * width / blocks::BLOCK_WIDTH,
+                        sub_row * height / rows,
+                        (sub_column + 1) * width / blocks::BLOCK_WIDTH,
+                        (sub_row + 1) * height / rows,
+                    );
+                }
+            }
+        }
+
+        return Some(coverage);
+    }
+
+    let bits = (symbol as u32).checked_sub(braille::BRAILLE_BASE)?;
+    if bits > 0xFF {
+        return None;
+    }
+
+    let (dot_width, dot_height) = (width / braille::CELL_WIDTH, height / braille::CELL_HEIGHT);
+    let dot_size = (dot_width.min(dot_height) / 2).max(1);
+    for (dy, dot_bits_row) in braille::DOT_BITS.iter().enumerate() {
+        for (dx, dot_bit) in dot_bits_row.iter().enumerate() {
+            if bits & dot_bit != 0 {
+                let (left, top) = (
+                    dx as u32 * dot_width + dot_width.saturating_sub(dot_size) / 2,
+                    dy as u32 * dot_height + dot_height.saturating_sub(dot_size) / 2,
+                );
+                fill(left, top, left + dot_size, top + dot_size);
+            }
+        }
+    }
+
+    Some(coverage)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, Rgba};
+
+    use super::{get_shape_coverage, render, RasterOptions};
+    use crate::{Cell, ColorDepth, SymbolizeError, SymbolizeResult};
+
+    #[test]
+    fn draws_cells_with_their_colors() {
+        let (red, blue) = (Rgb([255, 0, 0]), Rgb([0, 0, 255]));
+        let result = SymbolizeResult {
+            cells: vec![vec![
+                Cell::new('▀', red, Some(blue)),
+                Cell::new(' ', red, None),
+            ]],
+            color_depth: ColorDepth::TrueColor,
+            legend: vec![],
+        };
+        let image = render(&result, &RasterOptions::default()).unwrap();
+
+        assert_eq!(image.dimensions(), (16, 16));
+        assert_eq!(*image.get_pixel(3, 2), Rgba([255, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(3, 12), Rgba([0, 0, 255, 255]));
+        assert_eq!(*image.get_pixel(12, 8), Rgba([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn draws_symbols_with_builtin_font() {
+        let result = SymbolizeResult {
+            cells: vec![vec![Cell::new('#', Rgb([255, 0, 0]), None)]],
+            color_depth: ColorDepth::None,
+            legend: vec![],
+        };
+        let image = render(&result, &RasterOptions::default()).unwrap();
+
+        // without colors the symbol is drawn with the default foreground
+        assert!(image
+            .pixels()
+            .any(|pixel| *pixel == Rgba([229, 229, 229, 255])));
+        assert!(image.pixels().any(|pixel| *pixel == Rgba([0, 0, 0, 255])));
+    }
+
+    #[test]
+    fn draws_braille_dots() {
+        let coverage = get_shape_coverage('⠁', 8, 16).unwrap();
+        let lit: Vec<(usize, usize)> = (0..16)
+            .flat_map(|y| (0..8).map(move |x| (x, y)))
+            .filter(|(x, y)| coverage[y * 8 + x] > 0.0)
+            .collect();
+
+        assert_eq!(lit, vec![(1, 1), (2, 1), (1, 2), (2, 2)]);
+        assert!(get_shape_coverage('a', 8, 16).is_none());
+    }
+
+    #[test]
+    fn draws_braille_into_cells_smaller_than_dots() {
+        assert_eq!(get_shape_coverage('⣿', 1, 16).unwrap().len(), 16);
+        assert_eq!(get_shape_coverage('⣿', 1, 1).unwrap(), vec![1.0]);
+        assert!(get_shape_coverage('⣿', 0, 0).unwrap().is_empty());
+
+        let result = SymbolizeResult {
+            cells: vec![vec![Cell::new('⠁', Rgb([255, 0, 0]), None); 2]],
+            color_depth: ColorDepth::TrueColor,
+            legend: vec![],
+        };
+        let options = RasterOptions {
+            cell_width: 1,
+            ..Default::default()
+        };
+        assert_eq!(render(&result, &options).unwrap().dimensions(), (2, 16));
+    }
+
+    #[test]
+    fn returns_error_if_image_is_too_large() {
+        let result = SymbolizeResult {
+            cells: vec![vec![Cell::new('#', Rgb([255, 0, 0]), None); 2]; 2],
+            color_depth: ColorDepth::None,
+            legend: vec![],
+        };
+
+        for (cell_width, cell_height) in [(u32::MAX, 1), (1 << 16, 1 << 16)] {
+            let options = RasterOptions {
+                cell_width,
+                cell_height,
+                ..Default::default()
+            };
+
+            assert!(matches!(
+                render(&result, &options),
+                Err(SymbolizeError::OversizedOutput)
+            ));
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use image::Rgb;
+
+use crate::{
+    blocks::{self, get_block_shape},
+    html::{escape, get_hex},
+    Cell, ColorDepth, SymbolizeResult,
+};
+
+/// Options of the SVG output, see [`SymbolizeResult::to_svg`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgOptions {
+    /// Font family of the symbols, ideally a monospace one
+    pub font_family: String,
+    /// Font size of the symbols in user units
+    pub font_size: f32,
+    /// Width of a cell in user units
+    pub cell_width: f32,
+    /// Height of a cell in user units
+    pub cell_height: f32,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            font_family: "monospace".to_string(),
+            font_size: 12.0,
+            cell_width: 7.2,
+            cell_height: 14.4,
+        }
+    }
+}
+
+/// Renders the grid as an SVG image with the viewBox of the grid size.
+///
+/// Backgrounds are drawn as rects merged over runs of identically colored cells,
+/// block elements (e.g. of the block modes) as rects of their sub-pixels so they join seamlessly,
+/// other symbols as `<tspan>`s positioned at their cells.
+pub(crate) fn render(result: &SymbolizeResult, options: &SvgOptions) -> String {
+    let colorize = result.color_depth != ColorDepth::None;
+    let (cell_width, cell_height) = (options.cell_width, options.cell_height);
+    let columns = result.cells.iter().map(Vec::len).max().unwrap_or(0);
+    let (width, height) = (
+        get_number(columns as f32 * cell_width),
+        get_number(result.cells.len() as f32 * cell_height),
+    );
+
+    let fill = |color: Option<Rgb<u8>>| match color {
+        Some(color) if colorize => format!(" fill=\"{}\"", get_hex(&color)),
+        _ => String::new(),
+    };
+
+    let (mut rects, mut texts) = (vec![], vec![]);
+    for (row_idx, row) in result.cells.iter().enumerate() {
+        let y = row_idx as f32 * cell_height;
+
+        if colorize {
+            for (start, end, bg) in get_runs(row, |cell| cell.bg) {
+                if let Some(bg) = bg {
+                    rects.push(get_rect(
+                        start as f32 * cell_width,
+                        y,
+                        (end - start) as f32 * cell_width,
+                        cell_height,
+                        &fill(Some(bg)),
+                    ));
+                }
+            }
+        }
+
+        let mut spans = vec![];
+        for (start, end, fg) in get_runs(row, |cell| cell.fg) {
+            let mut xs = vec![];
+            let mut symbols = String::new();
+            for (column, cell) in row.iter().enumerate().take(end).skip(start) {
+                let x = column as f32 * cell_width;
+                match get_block_shape(cell.symbol) {
+                    Some((layout, pattern)) => {
+                        let sub_height = cell_height / layout.height() as f32;
+                        let sub_width = cell_width / blocks::BLOCK_WIDTH as f32;
+                        for sub_row in 0..layout.height() {
+                            let bits = pattern >> (sub_row * blocks::BLOCK_WIDTH) & 0b11;
+                            let (sub_x, sub_columns) = match bits {
+                                0b01 => (x, 1.0),
+                                0b10 => (x + sub_width, 1.0),
+                                0b11 => (x, 2.0),
+                                _ => continue,
+                            };
+                            rects.push(get_rect(
+                                sub_x,
+                                y + sub_row as f32 * sub_height,
+                                sub_columns * sub_width,
+                                sub_height,
+                                &fill(fg),
+                            ));
+                        }
+                    }
+                    None => {
+                        xs.push(get_number(x));
+                        symbols.push_str(&escape(&cell.symbol.to_string()));
+                    }
+                }
+            }
+
+            if !symbols.is_empty() {
+                spans.push(format!(
+                    "<tspan x=\"{}\"{}>{}</tspan>",
+                    xs.join(" "),
+                    fill(fg),
+                    symbols
+                ));
+            }
+        }
+
+        if !spans.is_empty() {
+            texts.push(format!(
+                "<text y=\"{}\" dominant-baseline=\"central\">{}</text>",
+                get_number(y + cell_height / 2.0),
+                spans.concat()
+            ));
+        }
+    }
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" \
+         font-family=\"{}\" font-size=\"{}\">\n",
+        width,
+        height,
+        width,
+        height,
+        escape(&options.font_family),
+        get_number(options.font_size)
+    );
+    if !rects.is_empty() {
+        svg.push_str(&format!(
+            "<g shape-rendering=\"crispEdges\">\n{}\n</g>\n",
+            rects.join("\n")
+        ));
+    }
+    for text in texts {
+        svg.push_str(&text);
+        svg.push('\n');
+    }
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+/// Splits the row into runs of cells with the same color as `(start, end, color)`
+fn get_runs(
+    row: &[Cell],
+    color: impl Fn(&Cell) -> Option<Rgb<u8>>,
+) -> Vec<(usize, usize, Option<Rgb<u8>>)> {
+    let mut runs: Vec<(usize, usize, Option<Rgb<u8>>)> = vec![];
+    for (idx, cell) in row.iter().enumerate() {
+        match runs.last_mut() {
+            Some((_, end, run_color)) if *run_color == color(cell) => *end = idx + 1,
+            _ => runs.push((idx, idx + 1, color(cell))),
+        }
+    }
+
+    runs
+}
+
+fn get_rect(x: f32, y: f32, width: f32, height: f32, fill: &str) -> String {
+    format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"{}/>",
+        get_number(x),
+        get_number(y),
+        get_number(width),
+        get_number(height),
+        fill
+    )
+}
+
+/// Formats the coordinate with at most two decimals, so float errors don't bloat the output
+fn get_number(value: f32) -> String {
+    let formatted = format!("{:.2}", value);
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgb;
+
+    use super::{get_number, render, SvgOptions};
+    use crate::{Cell, ColorDepth, SymbolizeResult};
+
+    #[test]
+    fn lays_out_symbols_and_blocks() {
+        let (red, blue) = (Rgb([255, 0, 0]), Rgb([0, 0, 255]));
+        let result = SymbolizeResult {
+            cells: vec![vec![
+                Cell::new('<', red, None),
+                Cell::new('a', red, None),
+                Cell::new('▀', blue, Some(red)),
+            ]],
+            color_depth: ColorDepth::TrueColor,
+            legend: vec![],
+        };
+        let svg = render(
+            &result,
+            &SvgOptions {
+                cell_width: 10.0,
+                cell_height: 20.0,
+                ..Default::default()
+            },
+        );
+
+        assert!(svg.contains("width=\"30\" height=\"20\" viewBox=\"0 0 30 20\""));
+        assert!(
+            svg.contains("<rect x=\"20\" y=\"0\" width=\"10\" height=\"20\" fill=\"#ff0000\"/>")
+        );
+        assert!(
+            svg.contains("<rect x=\"20\" y=\"0\" width=\"10\" height=\"10\" fill=\"#0000ff\"/>")
+        );
+        assert!(svg.contains(
+            "<text y=\"10\" dominant-baseline=\"central\">\
+             <tspan x=\"0 10\" fill=\"#ff0000\">&lt;a</tspan></text>"
+        ));
+    }
+
+    #[test]
+    fn trims_coordinates() {
+        assert_eq!(get_number(7.2 * 3.0), "21.6");
+        assert_eq!(get_number(14.0), "14");
+        assert_eq!(get_number(0.0), "0");
+    }
+}
@@ -0,0 +1,209 @@
+use image::{imageops::FilterType, DynamicImage, Frame};
+
+use crate::{
+    symbolize_with_options, AlphaOptions, Background, ColorDepth, DistanceMetric, Mode,
+    SymbolizeError, SymbolizeResult, SymbolizedFrames, DEFAULT_CELL_ASPECT_RATIO,
+};
+
+/// Symbols used when no palette is given, from the lightest to the densest one
+pub const DEFAULT_PALETTE: &[char] = &[' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+/// Everything that defines how an image is symbolized, see [`Symbolizer`] for a builder
+#[derive(Debug, Clone)]
+pub struct SymbolizeOptions {
+    /// Size of the result relative to the original image, ignored when `columns` or `rows` is set
+    pub scale: f32,
+    /// Maximum amount of columns of the result, the scale is computed with [`crate::get_scale_to_fit`]
+    pub columns: Option<u32>,
+    /// Maximum amount of rows of the result, the scale is computed with [`crate::get_scale_to_fit`]
+    pub rows: Option<u32>,
+    /// Symbols the image is drawn with, how they are picked depends on the mode
+    pub palette: Vec<char>,
+    /// Filter used when the image is scaled
+    pub filter_type: FilterType,
+    /// Colors the result is rendered with, [`ColorDepth::None`] leaves it uncolored
+    pub color_depth: ColorDepth,
+    /// Defines whether the cell colors are put to the background of the cells
+    pub background: Background,
+    /// Defines how the pixels of the image are turned into symbols
+    pub mode: Mode,
+    /// Defines how a pixel is matched with the palette colors
+    pub metric: DistanceMetric,
+    /// Height of a character cell divided by its width, e.g. `1.0` for square cells
+    pub cell_aspect_ratio: f32,
+    /// Defines how the transparent pixels are drawn, [`None`] discards the alpha channel
+    pub alpha: Option<AlphaOptions>,
+}
+
+impl Default for SymbolizeOptions {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            columns: None,
+            rows: None,
+            palette: DEFAULT_PALETTE.to_vec(),
+            filter_type: FilterType::Nearest,
+            color_depth: ColorDepth::None,
+            background: Background::default(),
+            mode: Mode::default(),
+            metric: DistanceMetric::default(),
+            cell_aspect_ratio: DEFAULT_CELL_ASPECT_RATIO,
+            alpha: None,
+        }
+    }
+}
+
+/// Builder of [`SymbolizeOptions`] that symbolizes images with them
+///
+/// ```ignore
+/// let result = Symbolizer::new()
+///     .scale(0.1)
+///     .palette(&[' ', '@', '#'])
+///     .color_depth(ColorDepth::TrueColor)
+///     .symbolize(open("./path/to/image.png")?)?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Symbolizer {
+    options: SymbolizeOptions,
+}
+
+impl From<SymbolizeOptions> for Symbolizer {
+    fn from(options: SymbolizeOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl Symbolizer {
+    /// Creates a symbolizer with the default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options the images are symbolized with
+    pub fn options(&self) -> &SymbolizeOptions {
+        &self.options
+    }
+
+    /// Sets the size of the result relative to the original image
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.options.scale = scale;
+        self
+    }
+
+    /// Fits the result into the budget of columns and rows instead of scaling it,
+    /// an absent budget doesn't limit its dimension
+    pub fn fit(mut self, columns: Option<u32>, rows: Option<u32>) -> Self {
+        self.options.columns = columns;
+        self.options.rows = rows;
+        self
+    }
+
+    /// Sets the symbols the image is drawn with
+    pub fn palette(mut self, palette: &[char]) -> Self {
+        self.options.palette = palette.to_vec();
+        self
+    }
+
+    /// Sets the filter used when the image is scaled
+    pub fn filter_type(mut self, filter_type: FilterType) -> Self {
+        self.options.filter_type = filter_type;
+        self
+    }
+
+    /// Sets the colors the result is rendered with
+    pub fn color_depth(mut self, color_depth: ColorDepth) -> Self {
+        self.options.color_depth = color_depth;
+        self
+    }
+
+    /// Colorizes the result with 24-bit colors or leaves it uncolored
+    pub fn colorize(self, colorize: bool) -> Self {
+        self.color_depth(if colorize {
+            ColorDepth::TrueColor
+        } else {
+            ColorDepth::None
+        })
+    }
+
+    /// Sets whether the cell colors are put to the background of the cells
+    pub fn background(mut self, background: Background) -> Self {
+        self.options.background = background;
+        self
+    }
+
+    /// Sets the rendering mode
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.options.mode = mode;
+        self
+    }
+
+    /// Sets how a pixel is matched with the palette colors
+    pub fn metric(mut self, metric: DistanceMetric) -> Self {
+        self.options.metric = metric;
+        self
+    }
+
+    /// Sets the height of a character cell divided by its width
+    pub fn cell_aspect_ratio(mut self, cell_aspect_ratio: f32) -> Self {
+        self.options.cell_aspect_ratio = cell_aspect_ratio;
+        self
+    }
+
+    /// Sets how the transparent pixels are drawn, [`None`] discards the alpha channel
+    pub fn alpha(mut self, alpha: Option<AlphaOptions>) -> Self {
+        self.options.alpha = alpha;
+        self
+    }
+
+    /// Turns the image into text art
+    pub fn symbolize(&self, image: DynamicImage) -> Result<SymbolizeResult, SymbolizeError> {
+        symbolize_with_options(image, &self.options, None)
+    }
+
+    /// Turns every frame of an animation into text art, e.g. the frames of [`crate::open_frames`].
+    /// The options are validated before any frame is symbolized.
+    pub fn symbolize_frames(&self, frames: Vec<Frame>) -> Result<SymbolizedFrames, SymbolizeError> {
+        SymbolizedFrames::new(frames, self.options.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::open;
+
+    use super::{SymbolizeOptions, Symbolizer};
+    use crate::{get_scale_to_fit, DEFAULT_CELL_ASPECT_RATIO};
+
+    #[test]
+    fn fits_result_into_budget_over_scale() {
+        let image = open("./test-data/ferris.png").unwrap();
+        let scale = get_scale_to_fit(
+            (image.width(), image.height()),
+            Some(80),
+            Some(10),
+            DEFAULT_CELL_ASPECT_RATIO,
+        );
+
+        let fitted = Symbolizer::new()
+            .scale(5.0)
+            .fit(Some(80), Some(10))
+            .symbolize(image.clone())
+            .unwrap();
+        let scaled = Symbolizer::new().scale(scale).symbolize(image).unwrap();
+
+        assert_eq!(fitted, scaled);
+    }
+
+    #[test]
+    fn builds_options_from_defaults() {
+        let options = SymbolizeOptions {
+            scale: 0.01,
+            ..Default::default()
+        };
+        let symbolizer = Symbolizer::from(options.clone()).colorize(true);
+
+        assert_eq!(symbolizer.options().scale, options.scale);
+        assert_eq!(symbolizer.options().palette, options.palette);
+        assert_ne!(symbolizer.options().color_depth, options.color_depth);
+    }
+}